@@ -1,7 +1,59 @@
+pub mod oversampling;
+pub mod processor;
+pub mod spectral;
+
+use oversampling::Oversampler;
+use processor::Processor;
+
+// added into the recursive paths so a decaying tail settles on a tiny value instead of sliding into
+// denormals, which are very slow on x86. far below anything audible
+const DENORMAL_OFFSET: f32 = 1.0e-18;
+
+// sets flush-to-zero and denormals-are-zero for as long as it's alive, then puts the old mode back
+pub struct DenormalGuard {
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    csr: u32,
+}
+
+impl DenormalGuard {
+    #[allow(deprecated)]
+    pub fn new() -> Self {
+        #[cfg(target_arch = "x86")]
+        use std::arch::x86::{_mm_getcsr, _mm_setcsr};
+        #[cfg(target_arch = "x86_64")]
+        use std::arch::x86_64::{_mm_getcsr, _mm_setcsr};
+
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        {
+            // ftz is bit 15, daz is bit 6
+            let csr = unsafe { _mm_getcsr() };
+            unsafe { _mm_setcsr(csr | 0x8040) };
+            Self { csr }
+        }
+        #[cfg(not(any(target_arch = "x86", target_arch = "x86_64")))]
+        Self {}
+    }
+}
+
+impl Drop for DenormalGuard {
+    #[allow(deprecated)]
+    fn drop(&mut self) {
+        #[cfg(target_arch = "x86")]
+        unsafe {
+            std::arch::x86::_mm_setcsr(self.csr)
+        };
+        #[cfg(target_arch = "x86_64")]
+        unsafe {
+            std::arch::x86_64::_mm_setcsr(self.csr)
+        };
+    }
+}
+
 pub struct Downsampler {
     amt: i32,
     count: i32,
     stored_sample: f32,
+    ratio: f32, //how much of the held sample to use instead of the input
 }
 
 impl Downsampler {
@@ -10,20 +62,31 @@ impl Downsampler {
             amt,
             count: 0,
             stored_sample: 0.0,
+            ratio: 0.0,
         }
     }
 
-    pub fn process_sample(&mut self, input: f32, ratio: f32) -> f32 {
+    pub fn set_amt(&mut self, amt: i32) {
+        self.amt = amt;
+    }
+    pub fn set_ratio(&mut self, ratio: f32) {
+        self.ratio = ratio;
+    }
+}
+
+impl Processor for Downsampler {
+    fn reset(&mut self) {
+        self.count = 0;
+        self.stored_sample = 0.0;
+    }
+
+    fn process_sample(&mut self, input: f32) -> f32 {
         self.count = (self.count + 2) % (self.amt + 1);
         if self.count == 0 {
             self.stored_sample = input;
         }
 
-        (ratio * self.stored_sample) + ((1.0 - ratio) * input)
-    }
-
-    pub fn set_amt(&mut self, amt: i32) {
-        self.amt = amt;
+        (self.ratio * self.stored_sample) + ((1.0 - self.ratio) * input)
     }
 }
 
@@ -36,7 +99,10 @@ pub struct Delay {
 
 impl Delay {
     pub fn new(sample_rate: f32, time: i32) -> Self {
-        let length: usize = (sample_rate / 4.0) as usize;
+        Self::with_length((sample_rate / 4.0) as usize, time)
+    }
+
+    pub fn with_length(length: usize, time: i32) -> Self {
         Self {
             buffer: vec![0.0; length],
             input_i: 0,
@@ -46,10 +112,20 @@ impl Delay {
     }
 
     pub fn set_time(&mut self, time: i32) {
-        self.output_i = (-time as i32).rem_euclid(self.buffer.len() as i32) as usize;
+        self.output_i = (self.input_i as i32 - time).rem_euclid(self.buffer.len() as i32) as usize;
+    }
+}
+
+impl Processor for Delay {
+    /// Silences the buffer, the delay time stays the same.
+    fn reset(&mut self) {
+        for sample in self.buffer.iter_mut() {
+            *sample = 0.0;
+        }
+        self.former_output = 0.0;
     }
 
-    pub fn process_sample(&mut self, input: f32) -> f32 {
+    fn process_sample(&mut self, input: f32) -> f32 {
         self.buffer[self.input_i] = input;
         self.former_output = self.buffer[self.output_i];
 
@@ -86,11 +162,20 @@ impl DelayWithFeedback {
         self.delay.set_time(time);
         self.feedback_delay.set_time(time);
     }
+}
 
-    pub fn process_sample(&mut self, input: f32) -> f32 {
+impl Processor for DelayWithFeedback {
+    fn reset(&mut self) {
+        self.delay.reset();
+        self.feedback_delay.reset();
+        self.former = 0.0;
+        self.former_feedback = 0.0;
+    }
+
+    fn process_sample(&mut self, input: f32) -> f32 {
         self.former = self
             .delay
-            .process_sample(input + (self.feedback * self.former_feedback));
+            .process_sample(input + (self.feedback * self.former_feedback) + DENORMAL_OFFSET);
         self.former_feedback = self.feedback_delay.process_sample(self.former);
 
         self.former
@@ -106,6 +191,14 @@ pub struct DegradedDelay {
     a: f32,
     ratio: f32,
     downsampler: Downsampler,
+    former_damped: f32,       //former output of the damping filter
+    amt: i32,                 //downsampling amount at the host rate
+    oversampler: Oversampler, //around the downsampler so its steps don't alias
+    freeze: f32,              //0.0 is normal, 1.0 is fully frozen (no input, lossless feedback)
+    shifter: PitchShifter,
+    shimmer: f32,
+    tone: f32,
+    former_shifted: f32, //former output of the tone filter on the shifted path
 }
 
 impl DegradedDelay {
@@ -119,6 +212,14 @@ impl DegradedDelay {
             a: 1.0,
             ratio: 0.0,
             downsampler: Downsampler::new(0),
+            former_damped: 0.0,
+            amt: 0,
+            oversampler: Oversampler::new(),
+            freeze: 0.0,
+            shifter: PitchShifter::new(sample_rate),
+            shimmer: 0.0,
+            tone: 1.0,
+            former_shifted: 0.0,
         }
     }
 
@@ -136,23 +237,170 @@ impl DegradedDelay {
         self.ratio = ratio;
     }
     pub fn set_amt(&mut self, amt: i32) {
-        self.downsampler.set_amt(amt);
+        self.amt = amt;
+        let factor = self.oversampler.factor() as i32;
+        if factor == 1 {
+            self.downsampler.set_amt(amt);
+        } else {
+            // the downsampler holds for (amt + 1) / 2 samples when that divides evenly, or amt + 1
+            // when it doesn't. hold for the same time at the oversampled rate
+            let hold = if (amt + 1) % 2 == 0 {
+                (amt + 1) / 2
+            } else {
+                amt + 1
+            };
+            self.downsampler.set_amt((2 * hold * factor) - 1);
+        }
+    }
+
+    /// 1, 2, 4 or 8.
+    pub fn set_oversampling(&mut self, factor: usize) {
+        if factor != self.oversampler.factor() {
+            self.oversampler.set_factor(factor);
+            self.set_amt(self.amt);
+        }
+    }
+    pub fn set_freeze(&mut self, freeze: f32) {
+        self.freeze = freeze.clamp(0.0, 1.0);
+    }
+    pub fn set_shimmer(&mut self, shimmer: f32) {
+        self.shimmer = shimmer;
     }
+    pub fn set_semitones(&mut self, semitones: f32) {
+        self.shifter.set_semitones(semitones);
+    }
+    pub fn set_tone(&mut self, tone: f32) {
+        self.tone = tone;
+    }
+}
+
+impl Processor for DegradedDelay {
+    // the delay times are in samples and stay as they are
+    fn prepare(&mut self, sample_rate: f32, max_block: usize) {
+        self.shifter.prepare(sample_rate, max_block);
+    }
+
+    fn reset(&mut self) {
+        self.delay.reset();
+        self.feedback_delay.reset();
+        self.former = 0.0;
+        self.former_feedback = 0.0;
+        self.former_damped = 0.0;
+        self.downsampler.reset();
+        self.oversampler.reset();
+        self.shifter.reset();
+        self.former_shifted = 0.0;
+    }
+
+    fn process_sample(&mut self, input: f32) -> f32 {
+        // while frozen, fade the input out and the feedback up to unity
+        let feedback = self.feedback + ((1.0 - self.feedback) * self.freeze);
 
-    pub fn process_sample(&mut self, input: f32) -> f32 {
         self.former = self
             .delay
-            .process_sample(input + (self.feedback * self.former_feedback));
-        self.former_feedback = self.downsampler.process_sample(
-            ((1.0 - self.a) * self.former_feedback)
-                + (self.feedback_delay.process_sample(self.former) * self.a),
-            self.ratio,
-        );
+            .process_sample((input * (1.0 - self.freeze)) + (feedback * self.former_feedback));
+        let fed_back = self.feedback_delay.process_sample(self.former);
+        // fully frozen, the loop is just the two delay lines so nothing is lost going round it.
+        // the shimmer, tone, damping and degradation stop where they are until it thaws
+        if self.freeze >= 1.0 {
+            self.former_feedback = fed_back;
+            return self.former;
+        }
+
+        let mut processed = fed_back;
+        if self.shimmer > 0.0 {
+            let shifted = self.shifter.process_sample(processed);
+            self.former_shifted += ((shifted - self.former_shifted) * self.tone) + DENORMAL_OFFSET;
+            processed = ((1.0 - self.shimmer) * processed) + (self.shimmer * self.former_shifted);
+        }
+        let damped = ((1.0 - self.a) * self.former_damped) + (processed * self.a) + DENORMAL_OFFSET;
+        self.downsampler.set_ratio(self.ratio);
+        let downsampler = &mut self.downsampler;
+        let processed = self
+            .oversampler
+            .process_sample(damped, |sample| downsampler.process_sample(sample));
+        // oversampled, the downsampler's output comes back late, so the damping filter can't run on
+        // it the way it does at the host rate
+        self.former_damped = if self.oversampler.factor() == 1 {
+            processed
+        } else {
+            damped
+        };
+        // crossfade to the bypassed loop while freezing or thawing
+        self.former_feedback = processed + ((fed_back - processed) * self.freeze);
 
         self.former
     }
 }
 
+// delay-line pitch shifter: two taps sweep through a short window in opposite halves and are
+// crossfaded so one is always away from the jump
+pub struct PitchShifter {
+    buffer: Vec<f32>,
+    input_i: usize,
+    window: f32,
+    phase: f32,
+    ratio: f32,
+}
+
+impl PitchShifter {
+    pub fn new(sample_rate: f32) -> Self {
+        let window = sample_rate * 0.05;
+        Self {
+            buffer: vec![0.0; window as usize + 2],
+            input_i: 0,
+            window,
+            phase: 0.0,
+            ratio: 1.0,
+        }
+    }
+
+    pub fn set_semitones(&mut self, semitones: f32) {
+        self.ratio = (semitones / 12.0).exp2();
+    }
+
+    fn read(&self, delay: f32) -> f32 {
+        let length = self.buffer.len() as f32;
+        let position = (self.input_i as f32 - delay).rem_euclid(length);
+        let i = position as usize;
+        let fraction = position - i as f32;
+        let next = (i + 1) % self.buffer.len();
+
+        self.buffer[i] + ((self.buffer[next] - self.buffer[i]) * fraction)
+    }
+}
+
+impl Processor for PitchShifter {
+    fn prepare(&mut self, sample_rate: f32, _max_block: usize) {
+        let ratio = self.ratio;
+        *self = Self::new(sample_rate);
+        self.ratio = ratio;
+    }
+
+    fn reset(&mut self) {
+        for sample in self.buffer.iter_mut() {
+            *sample = 0.0;
+        }
+        self.phase = 0.0;
+    }
+
+    fn process_sample(&mut self, input: f32) -> f32 {
+        self.buffer[self.input_i] = input;
+
+        let mut output = 0.0;
+        for offset in [0.0, 0.5].iter() {
+            let phase = (self.phase + offset).fract();
+            let gain = (phase * std::f32::consts::PI).sin().powi(2);
+            output += self.read(phase * self.window) * gain;
+        }
+
+        self.phase = (self.phase + ((1.0 - self.ratio) / self.window)).rem_euclid(1.0);
+        self.input_i = (self.input_i + 1) % self.buffer.len();
+
+        output
+    }
+}
+
 pub struct Allpass {
     delay: DelayWithFeedback,
     g: f32,
@@ -173,25 +421,711 @@ impl Allpass {
         self.g = g;
         self.delay.set_feedback(g);
     }
+}
 
-    pub fn process_sample(&mut self, input: f32) -> f32 {
+impl Processor for Allpass {
+    fn reset(&mut self) {
+        self.delay.reset();
+    }
+
+    fn process_sample(&mut self, input: f32) -> f32 {
         (input * -self.g) + (self.delay.process_sample(input) * (1.0 - self.g.powi(2)))
     }
 }
 
+// lowpass with the same damping curve as the one inside DegradedDelay
+pub struct OnePole {
+    former: f32, //former output
+    a: f32,
+}
+
+impl OnePole {
+    pub fn new(damping: f32) -> Self {
+        Self {
+            former: 0.0,
+            a: 1.0 - damping,
+        }
+    }
+
+    pub fn set_damping(&mut self, damping: f32) {
+        self.a = 1.0 - damping;
+    }
+}
+
+impl Processor for OnePole {
+    fn reset(&mut self) {
+        self.former = 0.0;
+    }
+
+    fn process_sample(&mut self, input: f32) -> f32 {
+        self.former = ((1.0 - self.a) * self.former) + (input * self.a) + DENORMAL_OFFSET;
+        self.former
+    }
+}
+
+struct ReverseHead {
+    end: usize, //where in the buffer the window ended, playback reads backwards from here
+    position: usize,
+    length: usize,
+}
+
+impl ReverseHead {
+    // an empty window that has already finished fading out
+    fn finished(fade: usize) -> Self {
+        Self {
+            end: 0,
+            position: fade,
+            length: 0,
+        }
+    }
+}
+
+pub struct Reverser {
+    buffer: Vec<f32>,
+    input_i: usize,
+    length: usize, //length of the window currently being recorded
+    next_length: usize,
+    recorded: usize,
+    fade: usize,
+    current: ReverseHead,
+    previous: ReverseHead, //keeps reading past the start of its window while the current one fades in
+}
+
+impl Reverser {
+    pub fn new(max_length: usize, fade: usize) -> Self {
+        let max_length = max_length.max(1);
+        let fade = fade.max(1);
+        Self {
+            buffer: vec![0.0; (2 * (max_length + fade)) + 2],
+            input_i: 0,
+            length: max_length,
+            next_length: max_length,
+            recorded: 0,
+            fade,
+            current: ReverseHead::finished(fade),
+            previous: ReverseHead::finished(fade),
+        }
+    }
+
+    /// Takes effect at the start of the next window.
+    pub fn set_length(&mut self, length: usize) {
+        let max_length = ((self.buffer.len() - 2) / 2) - self.fade;
+        self.next_length = length.clamp(1, max_length);
+    }
+
+    fn read_head(&mut self, which: usize) -> f32 {
+        let head = if which == 0 {
+            &mut self.current
+        } else {
+            &mut self.previous
+        };
+        if head.position >= head.length + self.fade {
+            return 0.0;
+        }
+
+        let gain = if head.position < self.fade {
+            head.position as f32 / self.fade as f32
+        } else if head.position >= head.length {
+            1.0 - ((head.position - head.length) as f32 / self.fade as f32)
+        } else {
+            1.0
+        };
+        let len = self.buffer.len();
+        let i = (head.end + len - 1 - head.position) % len;
+        head.position += 1;
+
+        self.buffer[i] * gain
+    }
+}
+
+impl Processor for Reverser {
+    /// Silences the buffer and starts recording a new window.
+    fn reset(&mut self) {
+        for sample in self.buffer.iter_mut() {
+            *sample = 0.0;
+        }
+        self.recorded = 0;
+        self.length = self.next_length;
+        self.current = ReverseHead::finished(self.fade);
+        self.previous = ReverseHead::finished(self.fade);
+    }
+
+    fn process_sample(&mut self, input: f32) -> f32 {
+        self.buffer[self.input_i] = input;
+        self.input_i = (self.input_i + 1) % self.buffer.len();
+
+        let output = self.read_head(0) + self.read_head(1);
+
+        self.recorded += 1;
+        if self.recorded >= self.length {
+            self.previous = std::mem::replace(
+                &mut self.current,
+                ReverseHead {
+                    end: self.input_i,
+                    position: 0,
+                    length: self.length,
+                },
+            );
+            // if the window got shorter the old one is cut off, so start fading it out right away
+            self.previous.length = self.previous.length.min(self.previous.position);
+            self.recorded = 0;
+            self.length = self.next_length;
+        }
+
+        output
+    }
+}
+
+pub struct EnvelopeFollower {
+    envelope: f32,
+    attack: f32, //coefficients
+    release: f32,
+    attack_time: f32, //seconds, kept for when the sample rate changes
+    release_time: f32,
+    sample_rate: f32,
+}
+
+impl EnvelopeFollower {
+    pub fn new(sample_rate: f32, attack: f32, release: f32) -> Self {
+        let mut follower = Self {
+            envelope: 0.0,
+            attack: 0.0,
+            release: 0.0,
+            attack_time: attack,
+            release_time: release,
+            sample_rate,
+        };
+        follower.set_attack(attack);
+        follower.set_release(release);
+        follower
+    }
+
+    // times are in seconds
+    pub fn set_attack(&mut self, attack: f32) {
+        self.attack_time = attack;
+        self.attack = (-1.0 / (attack.max(1e-5) * self.sample_rate)).exp();
+    }
+    pub fn set_release(&mut self, release: f32) {
+        self.release_time = release;
+        self.release = (-1.0 / (release.max(1e-5) * self.sample_rate)).exp();
+    }
+}
+
+impl Processor for EnvelopeFollower {
+    fn prepare(&mut self, sample_rate: f32, _max_block: usize) {
+        self.sample_rate = sample_rate;
+        self.set_attack(self.attack_time);
+        self.set_release(self.release_time);
+    }
+
+    fn reset(&mut self) {
+        self.envelope = 0.0;
+    }
+
+    fn process_sample(&mut self, input: f32) -> f32 {
+        let level = input.abs();
+        let coefficient = if level > self.envelope {
+            self.attack
+        } else {
+            self.release
+        };
+        self.envelope = level + ((self.envelope - level) * coefficient);
+
+        self.envelope
+    }
+}
+
+pub struct Gate {
+    gain: f32,
+    open: bool,
+    threshold: f32,
+    hold: usize,
+    hold_count: usize,
+    attack: f32,
+    release: f32,
+    hold_time: f32, //seconds, kept for when the sample rate changes
+    attack_time: f32,
+    release_time: f32,
+    sample_rate: f32,
+}
+
+impl Gate {
+    pub fn new(sample_rate: f32) -> Self {
+        let mut gate = Self {
+            gain: 0.0,
+            open: false,
+            threshold: 0.0,
+            hold: 0,
+            hold_count: 0,
+            attack: 0.0,
+            release: 0.0,
+            hold_time: 0.0,
+            attack_time: 0.0,
+            release_time: 0.0,
+            sample_rate,
+        };
+        gate.set_attack(0.001);
+        gate.set_release(0.1);
+        gate
+    }
+
+    // threshold is linear, times are in seconds
+    pub fn set_threshold(&mut self, threshold: f32) {
+        self.threshold = threshold;
+    }
+    pub fn set_hold(&mut self, hold: f32) {
+        self.hold_time = hold;
+        self.hold = (hold.max(0.0) * self.sample_rate) as usize;
+    }
+    pub fn set_attack(&mut self, attack: f32) {
+        self.attack_time = attack;
+        self.attack = (-1.0 / (attack.max(1e-5) * self.sample_rate)).exp();
+    }
+    pub fn set_release(&mut self, release: f32) {
+        self.release_time = release;
+        self.release = (-1.0 / (release.max(1e-5) * self.sample_rate)).exp();
+    }
+
+    // whether the key is above the threshold or still inside the hold time
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+}
+
+impl Processor for Gate {
+    fn prepare(&mut self, sample_rate: f32, _max_block: usize) {
+        self.sample_rate = sample_rate;
+        self.set_hold(self.hold_time);
+        self.set_attack(self.attack_time);
+        self.set_release(self.release_time);
+    }
+
+    fn reset(&mut self) {
+        self.gain = 0.0;
+        self.open = false;
+        self.hold_count = 0;
+    }
+
+    // takes the level of the key signal and returns the gain to apply
+    fn process_sample(&mut self, key: f32) -> f32 {
+        self.open = if key >= self.threshold {
+            self.hold_count = self.hold;
+            true
+        } else if self.hold_count > 0 {
+            self.hold_count -= 1;
+            true
+        } else {
+            false
+        };
+
+        if self.open {
+            self.gain = 1.0 + ((self.gain - 1.0) * self.attack);
+        } else {
+            self.gain *= self.release;
+        }
+
+        self.gain
+    }
+}
+
+pub struct Ducker {
+    follower: EnvelopeFollower,
+    threshold: f32,
+    amount: f32,
+}
+
+impl Ducker {
+    pub fn new(sample_rate: f32) -> Self {
+        Self {
+            follower: EnvelopeFollower::new(sample_rate, 0.01, 0.25),
+            threshold: 0.0,
+            amount: 0.0,
+        }
+    }
+
+    // threshold is in decibels, times are in seconds
+    pub fn set_threshold(&mut self, threshold: f32) {
+        self.threshold = threshold;
+    }
+    pub fn set_amount(&mut self, amount: f32) {
+        self.amount = amount.clamp(0.0, 1.0);
+    }
+    pub fn set_attack(&mut self, attack: f32) {
+        self.follower.set_attack(attack);
+    }
+    pub fn set_release(&mut self, release: f32) {
+        self.follower.set_release(release);
+    }
+}
+
+impl Processor for Ducker {
+    fn prepare(&mut self, sample_rate: f32, max_block: usize) {
+        self.follower.prepare(sample_rate, max_block);
+    }
+
+    fn reset(&mut self) {
+        self.follower.reset();
+    }
+
+    // takes the key signal and returns the gain to apply
+    fn process_sample(&mut self, key: f32) -> f32 {
+        let envelope = self.follower.process_sample(key);
+        let over = (20.0 * envelope.max(1e-6).log10()) - self.threshold;
+        // fully ducked 6dB above the threshold
+        let reduction = (over / 6.0).clamp(0.0, 1.0);
+
+        1.0 - (self.amount * reduction)
+    }
+}
+
+#[derive(PartialEq, Clone, Copy)]
+pub enum LfoShape {
+    Legacy, //the original squared-phase chirp, kept so old sessions sound the same
+    Sine,
+    Triangle,
+    Saw,
+    Square,
+    SampleAndHold,
+    SmoothRandom,
+}
+
 pub struct Lfo {
     freq: f32,
     phase: f32,
+    phase_offset: f32,
+    spread: f32, //how far ahead the right channel runs, as a fraction of a cycle
+    former_phases: [f32; 2], //to catch each channel wrapping around for the random shapes
+    random_from: [f32; 2],
+    random_to: [f32; 2],
+    shape: LfoShape,
+    rng: Rng,
+    sample_rate: f32,
 }
 
 impl Lfo {
-    pub fn new(freq: f32) -> Self {
-        Self { freq, phase: 0.0 }
+    pub fn new(sample_rate: f32, freq: f32) -> Self {
+        Self {
+            freq,
+            phase: 0.0,
+            phase_offset: 0.0,
+            spread: 0.0,
+            former_phases: [0.0; 2],
+            random_from: [0.0; 2],
+            random_to: [0.0; 2],
+            shape: LfoShape::Legacy,
+            rng: Rng::new(0x5eed),
+            sample_rate,
+        }
     }
 
-    pub fn next_sample(&mut self, sample_rate: f32) -> f32 {
-        let output = (self.phase * std::f32::consts::TAU).powi(2).sin();
-        self.phase = (self.phase + self.freq / sample_rate).fract();
+    pub fn set_freq(&mut self, freq: f32) {
+        self.freq = freq;
+    }
+    pub fn set_shape(&mut self, shape: LfoShape) {
+        self.shape = shape;
+    }
+    pub fn set_phase_offset(&mut self, phase_offset: f32) {
+        self.phase_offset = phase_offset.rem_euclid(1.0);
+    }
+    pub fn set_spread(&mut self, spread: f32) {
+        self.spread = spread.rem_euclid(1.0);
+    }
+    pub fn set_phase(&mut self, phase: f32) {
+        self.phase = phase.rem_euclid(1.0);
+    }
+
+    /// Starts the cycle over from the phase offset.
+    pub fn reset(&mut self) {
+        self.phase = 0.0;
+        self.former_phases = [0.0; 2];
+        for channel in 0..2 {
+            self.random_from[channel] = self.random_to[channel];
+            self.random_to[channel] = (self.rng.next_f32() * 2.0) - 1.0;
+        }
+    }
+
+    fn value(&mut self, channel: usize) -> f32 {
+        let phase = (self.phase + self.phase_offset + (self.spread * channel as f32)).fract();
+        if phase < self.former_phases[channel] {
+            self.random_from[channel] = self.random_to[channel];
+            self.random_to[channel] = (self.rng.next_f32() * 2.0) - 1.0;
+        }
+        self.former_phases[channel] = phase;
+
+        match self.shape {
+            LfoShape::Legacy => (phase * std::f32::consts::TAU).powi(2).sin(),
+            LfoShape::Sine => (phase * std::f32::consts::TAU).sin(),
+            LfoShape::Triangle => 1.0 - (4.0 * (phase - 0.5).abs()),
+            LfoShape::Saw => (2.0 * phase) - 1.0,
+            LfoShape::Square => {
+                if phase < 0.5 {
+                    1.0
+                } else {
+                    -1.0
+                }
+            }
+            LfoShape::SampleAndHold => self.random_to[channel],
+            LfoShape::SmoothRandom => {
+                let blend = 0.5 - (0.5 * (phase * std::f32::consts::PI).cos());
+                self.random_from[channel]
+                    + ((self.random_to[channel] - self.random_from[channel]) * blend)
+            }
+        }
+    }
+
+    fn advance(&mut self) {
+        self.phase = (self.phase + self.freq / self.sample_rate).fract();
+    }
+
+    pub fn next_sample(&mut self) -> f32 {
+        let output = self.value(0);
+        self.advance();
+        output
+    }
+
+    // left and right, with the right channel offset by the spread
+    pub fn next_stereo(&mut self) -> (f32, f32) {
+        let output = (self.value(0), self.value(1));
+        self.advance();
         output
     }
 }
+
+pub struct Rng {
+    state: u32,
+}
+
+impl Rng {
+    pub fn new(seed: u32) -> Self {
+        // xorshift gets stuck on zero
+        Self { state: seed.max(1) }
+    }
+
+    /// Uniformly distributed between 0.0 and 1.0.
+    pub fn next_f32(&mut self) -> f32 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 17;
+        self.state ^= self.state << 5;
+        (self.state >> 8) as f32 / (1 << 24) as f32
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum SmootherKind {
+    OnePole, //exponential approach, `time` is the time constant
+    Linear,  //straight ramp that reaches the target after `time`
+}
+
+// takes the edge off parameter changes so they don't zipper
+pub struct Smoother {
+    kind: SmootherKind,
+    value: f32,
+    target: f32,
+    coefficient: f32,
+    step: f32,
+    ramp_length: usize,
+    remaining: usize,
+    started: bool,
+    time: f32,
+    sample_rate: f32,
+}
+
+impl Smoother {
+    pub fn new(sample_rate: f32, time: f32, kind: SmootherKind) -> Self {
+        let mut smoother = Self {
+            kind,
+            value: 0.0,
+            target: 0.0,
+            coefficient: 1.0,
+            step: 0.0,
+            ramp_length: 1,
+            remaining: 0,
+            started: false,
+            time,
+            sample_rate,
+        };
+        smoother.set_time(time);
+        smoother
+    }
+
+    pub fn set_time(&mut self, time: f32) {
+        self.time = time;
+        let samples = time * self.sample_rate;
+        self.coefficient = if samples > 1.0 {
+            1.0 - (-1.0 / samples).exp()
+        } else {
+            1.0
+        };
+        self.ramp_length = (samples as usize).max(1);
+    }
+
+    /// Jumps straight to `value`.
+    pub fn jump_to(&mut self, value: f32) {
+        self.value = value;
+        self.target = value;
+        self.remaining = 0;
+        self.started = true;
+    }
+}
+
+impl Processor for Smoother {
+    fn prepare(&mut self, sample_rate: f32, _max_block: usize) {
+        self.sample_rate = sample_rate;
+        self.set_time(self.time);
+    }
+
+    /// Jumps straight to whatever target comes next.
+    fn reset(&mut self) {
+        self.started = false;
+    }
+
+    fn process_sample(&mut self, target: f32) -> f32 {
+        // nothing to smooth from on the first sample
+        if !self.started {
+            self.jump_to(target);
+        }
+
+        match self.kind {
+            SmootherKind::OnePole => {
+                self.value += (target - self.value) * self.coefficient;
+            }
+            SmootherKind::Linear => {
+                if target != self.target {
+                    self.target = target;
+                    self.remaining = self.ramp_length;
+                    self.step = (target - self.value) / self.ramp_length as f32;
+                }
+                if self.remaining > 0 {
+                    self.remaining -= 1;
+                    self.value = if self.remaining == 0 {
+                        self.target
+                    } else {
+                        self.value + self.step
+                    };
+                }
+            }
+        }
+        self.value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE_RATE: f32 = 44100.0;
+
+    // the default topology's left channel, built straight from the units so their state can be
+    // looked at: four degraded delays in parallel into three allpasses
+    fn schroeder() -> (Vec<DegradedDelay>, Vec<Allpass>) {
+        let g = 0.7;
+        let combs = [1323, 1499, 1676, 1852]
+            .iter()
+            .map(|time| {
+                let mut comb = DegradedDelay::new(SAMPLE_RATE, *time, g);
+                comb.set_a(0.5);
+                comb.set_shimmer(0.3);
+                comb.set_semitones(12.0);
+                comb.set_tone(0.5);
+                comb
+            })
+            .collect();
+        let allpasses = [(4410, g), (2999, -g), (2646, g)]
+            .iter()
+            .map(|(time, g)| Allpass::new(SAMPLE_RATE, *time, *g))
+            .collect();
+        (combs, allpasses)
+    }
+
+    fn assert_normal(values: &[f32], what: &str) {
+        if let Some(value) = values.iter().find(|value| value.is_subnormal()) {
+            panic!("{} went subnormal ({:e})", what, value);
+        }
+    }
+
+    // with damping, degradation, shimmer and oversampling all on, a fully frozen loop should
+    // still hold its level indefinitely
+    #[test]
+    fn freeze_is_lossless() {
+        let mut comb = DegradedDelay::new(SAMPLE_RATE, 1323, 0.7);
+        comb.set_a(0.5);
+        comb.set_amt(4);
+        comb.set_ratio(0.5);
+        comb.set_oversampling(2);
+        comb.set_shimmer(0.3);
+        comb.set_semitones(12.0);
+        comb.set_tone(0.5);
+
+        let mut noise = 1u32;
+        for _ in 0..SAMPLE_RATE as usize / 10 {
+            noise = noise.wrapping_mul(1664525).wrapping_add(1013904223);
+            comb.process_sample((noise >> 8) as f32 / (1 << 24) as f32 - 0.5);
+        }
+        comb.set_freeze(1.0);
+        // a second is longer than the loop, so every second has the same peak unless it's losing
+        let peak = |comb: &mut DegradedDelay| {
+            (0..SAMPLE_RATE as usize)
+                .map(|_| comb.process_sample(1.0).abs())
+                .fold(0.0f32, f32::max)
+        };
+        let first = peak(&mut comb);
+        for _ in 0..10 {
+            peak(&mut comb);
+        }
+        let last = peak(&mut comb);
+        assert!(first > 0.0);
+        assert!(
+            ((last - first) / first).abs() < 1e-3,
+            "the frozen peak went from {} to {}",
+            first,
+            last
+        );
+    }
+
+    // no DenormalGuard here, so anything that decays into denormals shows up as one
+    #[test]
+    fn schroeder_tail_never_goes_subnormal() {
+        let (mut combs, mut allpasses) = schroeder();
+
+        for i in 0..(30.0 * SAMPLE_RATE) as usize {
+            let input = if i == 0 { 1.0 } else { 0.0 };
+            let mut output = combs
+                .iter_mut()
+                .map(|comb| comb.process_sample(input))
+                .sum::<f32>()
+                * 0.5;
+            for allpass in allpasses.iter_mut() {
+                output = allpass.process_sample(output);
+            }
+
+            assert_normal(&[output], "the output");
+            for comb in combs.iter() {
+                assert_normal(
+                    &[
+                        comb.former,
+                        comb.former_feedback,
+                        comb.former_damped,
+                        comb.former_shifted,
+                    ],
+                    "a degraded delay's filter state",
+                );
+            }
+            for allpass in allpasses.iter() {
+                assert_normal(
+                    &[allpass.delay.former, allpass.delay.former_feedback],
+                    "an allpass's state",
+                );
+            }
+        }
+
+        for comb in combs.iter() {
+            assert_normal(&comb.delay.buffer, "a degraded delay's line");
+            assert_normal(&comb.feedback_delay.buffer, "a degraded delay's line");
+            assert_normal(&comb.shifter.buffer, "the shimmer's line");
+        }
+        for allpass in allpasses.iter() {
+            assert_normal(&allpass.delay.delay.buffer, "an allpass's line");
+            assert_normal(&allpass.delay.feedback_delay.buffer, "an allpass's line");
+        }
+    }
+}
@@ -0,0 +1,222 @@
+// runs a nonlinear stage at 2x, 4x or 8x the host rate so it doesn't alias, using a cascade of
+// polyphase iir half-band filters. they're minimum phase, so unlike linear phase ones there's no
+// fixed delay the host would need to be told about, only a few samples of group delay in the bass
+
+pub const MAX_FACTOR: usize = 8;
+
+// allpass coefficients of each stage's half-band filter, alternating between the two branches.
+// the first stage needs a steep transition (0.05 of the oversampled rate either side of its
+// nyquist) for about 93 dB of rejection. later stages only have to reject what's above the
+// original nyquist, so they get by with a much wider transition and fewer coefficients
+const STAGE_1: [f32; 7] = [
+    0.045728147,
+    0.16808754,
+    0.3325011,
+    0.5044857,
+    0.66320205,
+    0.80378085,
+    0.93385583,
+];
+const STAGE_2: [f32; 4] = [0.07076595, 0.2578531, 0.51316756, 0.81731737];
+const STAGE_3: [f32; 3] = [0.08744162, 0.33144575, 0.72214043];
+const STAGES: [&[f32]; 3] = [&STAGE_1, &STAGE_2, &STAGE_3];
+
+// first order allpass in z^2, run at the lower rate where that's z
+#[derive(Clone, Copy)]
+struct Allpass {
+    coefficient: f32,
+    former_input: f32,
+    former_output: f32,
+}
+
+impl Allpass {
+    fn process_sample(&mut self, input: f32) -> f32 {
+        let output = (self.coefficient * (input - self.former_output)) + self.former_input;
+        self.former_input = input;
+        self.former_output = output;
+        output
+    }
+}
+
+// a chain of allpasses, one polyphase branch of a half-band filter
+#[derive(Clone)]
+struct Branch {
+    allpasses: Vec<Allpass>,
+}
+
+impl Branch {
+    fn new(coefficients: impl Iterator<Item = f32>) -> Self {
+        Self {
+            allpasses: coefficients
+                .map(|coefficient| Allpass {
+                    coefficient,
+                    former_input: 0.0,
+                    former_output: 0.0,
+                })
+                .collect(),
+        }
+    }
+
+    fn reset(&mut self) {
+        for allpass in self.allpasses.iter_mut() {
+            allpass.former_input = 0.0;
+            allpass.former_output = 0.0;
+        }
+    }
+
+    fn process_sample(&mut self, input: f32) -> f32 {
+        self.allpasses
+            .iter_mut()
+            .fold(input, |sample, allpass| allpass.process_sample(sample))
+    }
+}
+
+// a 2x half-band lowpass, half the sum of an even and an odd branch. upsampling and downsampling
+// each need their own copy of the branches since they run at the same time
+struct HalfBand {
+    up_even: Branch,
+    up_odd: Branch,
+    down_even: Branch,
+    down_odd: Branch,
+    former_odd: f32, //the odd branch's last output when downsampling, it lines up one sample late
+}
+
+impl HalfBand {
+    fn new(coefficients: &[f32]) -> Self {
+        let even = Branch::new(coefficients.iter().copied().step_by(2));
+        let odd = Branch::new(coefficients.iter().copied().skip(1).step_by(2));
+        Self {
+            up_even: even.clone(),
+            up_odd: odd.clone(),
+            down_even: even,
+            down_odd: odd,
+            former_odd: 0.0,
+        }
+    }
+
+    fn reset(&mut self) {
+        self.up_even.reset();
+        self.up_odd.reset();
+        self.down_even.reset();
+        self.down_odd.reset();
+        self.former_odd = 0.0;
+    }
+
+    // one sample in, two out
+    fn upsample(&mut self, input: f32) -> (f32, f32) {
+        (
+            self.up_even.process_sample(input),
+            self.up_odd.process_sample(input),
+        )
+    }
+
+    // two samples in, one out
+    fn downsample(&mut self, even: f32, odd: f32) -> f32 {
+        let output = 0.5 * (self.down_even.process_sample(even) + self.former_odd);
+        self.former_odd = self.down_odd.process_sample(odd);
+        output
+    }
+}
+
+pub struct Oversampler {
+    stages: Vec<HalfBand>,
+    active: usize, //how many of the stages are in use, the factor is 2 to the power of this
+}
+
+impl Oversampler {
+    pub fn new() -> Self {
+        Self {
+            stages: STAGES
+                .iter()
+                .map(|coefficients| HalfBand::new(coefficients))
+                .collect(),
+            active: 0,
+        }
+    }
+
+    /// 1, 2, 4 or 8, anything else is rounded down to one of those.
+    pub fn set_factor(&mut self, factor: usize) {
+        let active = (factor.clamp(1, MAX_FACTOR) as f32).log2() as usize;
+        if active != self.active {
+            self.active = active;
+            self.reset();
+        }
+    }
+
+    pub fn factor(&self) -> usize {
+        1 << self.active
+    }
+
+    pub fn reset(&mut self) {
+        for stage in self.stages.iter_mut() {
+            stage.reset();
+        }
+    }
+
+    // upsamples the input, runs `f` on every oversampled sample and brings the result back down
+    pub fn process_sample(&mut self, input: f32, mut f: impl FnMut(f32) -> f32) -> f32 {
+        if self.active == 0 {
+            return f(input);
+        }
+
+        let mut buffer = [0.0; MAX_FACTOR];
+        let mut upsampled = [0.0; MAX_FACTOR];
+        buffer[0] = input;
+        let mut length = 1;
+        for stage in self.stages[..self.active].iter_mut() {
+            // every stage sees `length` samples per host sample, in order
+            for k in 0..length {
+                let (even, odd) = stage.upsample(buffer[k]);
+                upsampled[2 * k] = even;
+                upsampled[(2 * k) + 1] = odd;
+            }
+            length *= 2;
+            buffer[..length].copy_from_slice(&upsampled[..length]);
+        }
+
+        for sample in buffer[..length].iter_mut() {
+            *sample = f(*sample);
+        }
+
+        for stage in self.stages[..self.active].iter_mut().rev() {
+            length /= 2;
+            for k in 0..length {
+                buffer[k] = stage.downsample(buffer[2 * k], buffer[(2 * k) + 1]);
+            }
+        }
+
+        buffer[0]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // with nothing in between, every factor should hand back what went in, only phase shifted
+    #[test]
+    fn oversampler_passes_the_audio_band() {
+        let sample_rate = 44100.0;
+        for factor in [2, 4, 8].iter() {
+            let mut oversampler = Oversampler::new();
+            oversampler.set_factor(*factor);
+            for freq in [100.0, 1000.0, 10000.0].iter() {
+                oversampler.reset();
+                let peak = (0..sample_rate as usize)
+                    .map(|i| {
+                        let phase = 2.0 * std::f32::consts::PI * freq * i as f32 / sample_rate;
+                        (i, oversampler.process_sample(phase.sin(), |sample| sample))
+                    })
+                    .filter(|(i, _)| *i > sample_rate as usize / 2)
+                    .fold(0.0f32, |peak, (_, sample)| peak.max(sample.abs()));
+                assert!(
+                    (peak - 1.0).abs() < 0.01,
+                    "{} Hz at {}x came out with a peak of {}",
+                    freq,
+                    factor,
+                    peak
+                );
+            }
+        }
+    }
+}
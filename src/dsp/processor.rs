@@ -0,0 +1,134 @@
+// the interface every mono dsp unit shares, and building blocks for wiring units together
+
+pub trait Processor {
+    /// Called before processing starts and whenever the sample rate changes. `max_block` is the most
+    /// samples one host process call covers, for anything that needs room for a whole block.
+    fn prepare(&mut self, _sample_rate: f32, _max_block: usize) {}
+
+    /// Clears anything left over from earlier input.
+    fn reset(&mut self);
+
+    fn process_sample(&mut self, input: f32) -> f32;
+}
+
+impl<P: Processor + ?Sized> Processor for Box<P> {
+    fn prepare(&mut self, sample_rate: f32, max_block: usize) {
+        (**self).prepare(sample_rate, max_block);
+    }
+    fn reset(&mut self) {
+        (**self).reset();
+    }
+    fn process_sample(&mut self, input: f32) -> f32 {
+        (**self).process_sample(input)
+    }
+}
+
+// `first` feeding into `second`
+pub struct Series<A, B> {
+    pub first: A,
+    pub second: B,
+}
+
+impl<A: Processor, B: Processor> Series<A, B> {
+    pub fn new(first: A, second: B) -> Self {
+        Self { first, second }
+    }
+}
+
+impl<A: Processor, B: Processor> Processor for Series<A, B> {
+    fn prepare(&mut self, sample_rate: f32, max_block: usize) {
+        self.first.prepare(sample_rate, max_block);
+        self.second.prepare(sample_rate, max_block);
+    }
+    fn reset(&mut self) {
+        self.first.reset();
+        self.second.reset();
+    }
+    fn process_sample(&mut self, input: f32) -> f32 {
+        self.second.process_sample(self.first.process_sample(input))
+    }
+}
+
+// both fed the same input, outputs summed
+pub struct Parallel<A, B> {
+    pub first: A,
+    pub second: B,
+}
+
+impl<A: Processor, B: Processor> Parallel<A, B> {
+    pub fn new(first: A, second: B) -> Self {
+        Self { first, second }
+    }
+}
+
+impl<A: Processor, B: Processor> Processor for Parallel<A, B> {
+    fn prepare(&mut self, sample_rate: f32, max_block: usize) {
+        self.first.prepare(sample_rate, max_block);
+        self.second.prepare(sample_rate, max_block);
+    }
+    fn reset(&mut self) {
+        self.first.reset();
+        self.second.reset();
+    }
+    fn process_sample(&mut self, input: f32) -> f32 {
+        self.first.process_sample(input) + self.second.process_sample(input)
+    }
+}
+
+// the output of `inner` is fed back into its input a sample later, scaled by `amount`. put a delay
+// inside for anything longer
+pub struct Feedback<P> {
+    pub inner: P,
+    amount: f32,
+    former: f32, //former output of inner
+}
+
+impl<P: Processor> Feedback<P> {
+    pub fn new(inner: P, amount: f32) -> Self {
+        Self {
+            inner,
+            amount,
+            former: 0.0,
+        }
+    }
+}
+
+impl<P: Processor> Processor for Feedback<P> {
+    fn prepare(&mut self, sample_rate: f32, max_block: usize) {
+        self.inner.prepare(sample_rate, max_block);
+    }
+    fn reset(&mut self) {
+        self.inner.reset();
+        self.former = 0.0;
+    }
+    fn process_sample(&mut self, input: f32) -> f32 {
+        self.former = self
+            .inner
+            .process_sample(input + (self.amount * self.former));
+        self.former
+    }
+}
+
+// crossfades between the input (0.0) and the output of `inner` (1.0)
+pub struct Mix<P> {
+    pub inner: P,
+    mix: f32,
+}
+
+impl<P: Processor> Mix<P> {
+    pub fn new(inner: P, mix: f32) -> Self {
+        Self { inner, mix }
+    }
+}
+
+impl<P: Processor> Processor for Mix<P> {
+    fn prepare(&mut self, sample_rate: f32, max_block: usize) {
+        self.inner.prepare(sample_rate, max_block);
+    }
+    fn reset(&mut self) {
+        self.inner.reset();
+    }
+    fn process_sample(&mut self, input: f32) -> f32 {
+        ((1.0 - self.mix) * input) + (self.mix * self.inner.process_sample(input))
+    }
+}
@@ -0,0 +1,254 @@
+use super::processor::Processor;
+use super::Rng;
+use std::f32::consts::TAU;
+
+// length of the analysis/resynthesis frames, in seconds (rounded up to a power of two in samples)
+const FRAME_TIME: f32 = 0.046;
+// how far apart frames are, as a fraction of the frame length
+const OVERLAP: usize = 4;
+// how long the frozen spectrum takes to fade in and out, in seconds
+const FADE_TIME: f32 = 0.05;
+// random phases add up by power rather than amplitude, and the hann window spreads each partial
+// over three bins. this brings a steady tone back to roughly its original level at 4x overlap
+const RESYNTHESIS_GAIN: f32 = 4.0 / 3.0;
+
+struct Fft {
+    size: usize,
+    bit_reverse: Vec<usize>,
+    twiddles: Vec<(f32, f32)>,
+}
+
+impl Fft {
+    fn new(size: usize) -> Self {
+        let bits = size.trailing_zeros();
+        Self {
+            size,
+            bit_reverse: (0..size)
+                .map(|i| i.reverse_bits() >> (usize::BITS - bits))
+                .collect(),
+            twiddles: (0..size / 2)
+                .map(|i| {
+                    let angle = -TAU * i as f32 / size as f32;
+                    (angle.cos(), angle.sin())
+                })
+                .collect(),
+        }
+    }
+
+    // in-place radix-2 transform, the inverse is scaled by 1/size
+    fn process(&self, re: &mut [f32], im: &mut [f32], inverse: bool) {
+        for i in 0..self.size {
+            let j = self.bit_reverse[i];
+            if j > i {
+                re.swap(i, j);
+                im.swap(i, j);
+            }
+        }
+
+        let mut len = 2;
+        while len <= self.size {
+            let stride = self.size / len;
+            for start in (0..self.size).step_by(len) {
+                for k in 0..len / 2 {
+                    let (w_re, mut w_im) = self.twiddles[k * stride];
+                    if inverse {
+                        w_im = -w_im;
+                    }
+                    let a = start + k;
+                    let b = a + len / 2;
+                    let t_re = (re[b] * w_re) - (im[b] * w_im);
+                    let t_im = (re[b] * w_im) + (im[b] * w_re);
+                    re[b] = re[a] - t_re;
+                    im[b] = im[a] - t_im;
+                    re[a] += t_re;
+                    im[a] += t_im;
+                }
+            }
+            len *= 2;
+        }
+
+        if inverse {
+            let scale = 1.0 / self.size as f32;
+            for i in 0..self.size {
+                re[i] *= scale;
+                im[i] *= scale;
+            }
+        }
+    }
+}
+
+/// Captures the magnitude spectrum of its input when frozen, and resynthesizes it with random
+/// phases (overlap-add) for as long as the freeze is held.
+pub struct SpectralFreeze {
+    fft: Fft,
+    window: Vec<f32>,
+    input: Vec<f32>, //ring buffer of the most recent frame of input
+    input_i: usize,
+    output: Vec<f32>, //overlap-add accumulator
+    output_i: usize,
+    hop: usize,
+    hop_count: usize,
+    captured: Vec<f32>, //magnitudes at the freeze moment, blurred a bit more every frame
+    magnitudes: Vec<f32>, //what actually gets resynthesized, smoothed towards captured
+    scratch_re: Vec<f32>,
+    scratch_im: Vec<f32>,
+    rng: Rng,
+    frozen: bool,
+    mix: f32, //crossfade between the input and the frozen spectrum
+    fade_step: f32,
+    smoothing: f32,
+    blur: f32,
+}
+
+impl SpectralFreeze {
+    pub fn new(sample_rate: f32, seed: u32) -> Self {
+        let size = ((sample_rate * FRAME_TIME) as usize).next_power_of_two();
+        Self {
+            fft: Fft::new(size),
+            window: (0..size)
+                .map(|i| 0.5 - (0.5 * (TAU * i as f32 / size as f32).cos()))
+                .collect(),
+            input: vec![0.0; size],
+            input_i: 0,
+            output: vec![0.0; size],
+            output_i: 0,
+            hop: size / OVERLAP,
+            hop_count: 0,
+            captured: vec![0.0; (size / 2) + 1],
+            magnitudes: vec![0.0; (size / 2) + 1],
+            scratch_re: vec![0.0; size],
+            scratch_im: vec![0.0; size],
+            rng: Rng::new(seed),
+            frozen: false,
+            mix: 0.0,
+            fade_step: 1.0 / (FADE_TIME * sample_rate),
+            smoothing: 0.5,
+            blur: 0.0,
+        }
+    }
+
+    pub fn set_frozen(&mut self, frozen: bool) {
+        if frozen && !self.frozen {
+            self.capture();
+        }
+        self.frozen = frozen;
+    }
+    /// How quickly (per frame, 0.0 to 1.0) the resynthesized magnitudes follow the captured ones.
+    pub fn set_smoothing(&mut self, smoothing: f32) {
+        self.smoothing = smoothing.clamp(0.0, 1.0);
+    }
+    /// How much the captured spectrum smears into neighbouring bins on every frame.
+    pub fn set_blur(&mut self, blur: f32) {
+        self.blur = blur.clamp(0.0, 1.0);
+    }
+
+    fn capture(&mut self) {
+        let size = self.fft.size;
+        for i in 0..size {
+            // oldest sample first
+            self.scratch_re[i] = self.input[(self.input_i + i) % size] * self.window[i];
+            self.scratch_im[i] = 0.0;
+        }
+        self.fft
+            .process(&mut self.scratch_re, &mut self.scratch_im, false);
+
+        for k in 0..self.captured.len() {
+            self.captured[k] = self.scratch_re[k].hypot(self.scratch_im[k]);
+        }
+        // start from the fully captured spectrum only if we weren't already resynthesizing
+        if self.mix == 0.0 {
+            self.magnitudes.copy_from_slice(&self.captured);
+        }
+    }
+
+    fn resynthesize(&mut self) {
+        let size = self.fft.size;
+        let bins = self.captured.len();
+
+        if self.blur > 0.0 {
+            let mut previous = self.captured[0];
+            for k in 1..bins - 1 {
+                let current = self.captured[k];
+                let average = (previous + current + self.captured[k + 1]) / 3.0;
+                self.captured[k] = current + ((average - current) * self.blur);
+                previous = current;
+            }
+        }
+
+        for k in 0..bins {
+            self.magnitudes[k] += (self.captured[k] - self.magnitudes[k]) * self.smoothing;
+
+            let phase = self.rng.next_f32() * TAU;
+            self.scratch_re[k] = self.magnitudes[k] * phase.cos();
+            self.scratch_im[k] = self.magnitudes[k] * phase.sin();
+        }
+        // dc and nyquist have to be real
+        self.scratch_im[0] = 0.0;
+        self.scratch_im[bins - 1] = 0.0;
+        for k in bins..size {
+            self.scratch_re[k] = self.scratch_re[size - k];
+            self.scratch_im[k] = -self.scratch_im[size - k];
+        }
+        self.fft
+            .process(&mut self.scratch_re, &mut self.scratch_im, true);
+
+        for i in 0..size {
+            self.output[(self.output_i + i) % size] +=
+                self.scratch_re[i] * self.window[i] * RESYNTHESIS_GAIN;
+        }
+    }
+}
+
+impl Processor for SpectralFreeze {
+    // the frame size stays what it was created with
+    fn prepare(&mut self, sample_rate: f32, _max_block: usize) {
+        self.fade_step = 1.0 / (FADE_TIME * sample_rate);
+    }
+
+    /// Forgets the input, the overlap-add tail and the captured spectrum. A freeze that's still held
+    /// captures again on the next call to `set_frozen`.
+    fn reset(&mut self) {
+        for buffer in [
+            &mut self.input,
+            &mut self.output,
+            &mut self.captured,
+            &mut self.magnitudes,
+        ]
+        .iter_mut()
+        {
+            for value in buffer.iter_mut() {
+                *value = 0.0;
+            }
+        }
+        self.hop_count = 0;
+        self.frozen = false;
+        self.mix = 0.0;
+    }
+
+    fn process_sample(&mut self, input: f32) -> f32 {
+        let size = self.fft.size;
+
+        self.input[self.input_i] = input;
+        self.input_i = (self.input_i + 1) % size;
+
+        if self.frozen {
+            self.mix = (self.mix + self.fade_step).min(1.0);
+        } else {
+            self.mix = (self.mix - self.fade_step).max(0.0);
+        }
+
+        self.hop_count += 1;
+        if self.hop_count >= self.hop {
+            self.hop_count = 0;
+            if self.mix > 0.0 {
+                self.resynthesize();
+            }
+        }
+
+        let frozen = self.output[self.output_i];
+        self.output[self.output_i] = 0.0;
+        self.output_i = (self.output_i + 1) % size;
+
+        (input * (1.0 - self.mix)) + (frozen * self.mix)
+    }
+}
@@ -1,9 +1,11 @@
 #![allow(incomplete_features)]
-#![feature(generic_associated_types)]
+#![feature(generic_associated_types, min_specialization)]
 
 use serde::{Deserialize, Serialize};
 
-use baseplug::{Model, Plugin, ProcessContext, UIFloatParam, UIModel, WindowOpenResult};
+use baseplug::{
+    MidiReceiver, Model, Plugin, ProcessContext, UIFloatParam, UIModel, WindowOpenResult,
+};
 use baseview::{Size, WindowOpenOptions, WindowScalePolicy};
 use raw_window_handle::HasRawWindowHandle;
 
@@ -11,10 +13,31 @@ use egui::CtxRef;
 use egui_baseview::{EguiWindow, Queue, RenderSettings, Settings};
 
 mod dsp;
-use dsp::{Allpass, DegradedDelay, Lfo};
+mod midi;
+mod modulation;
+mod preset;
+mod rebuild;
+mod shared;
+mod storage;
+mod topology;
+use dsp::oversampling::Oversampler;
+use dsp::processor::Processor;
+use dsp::spectral::SpectralFreeze;
+use dsp::{
+    DenormalGuard, Ducker, EnvelopeFollower, Gate, Lfo, LfoShape, Reverser, Smoother, SmootherKind,
+};
+use midi::{CcBinding, MidiState};
+use modulation::{ModSlot, ModSources};
+use preset::{Preset, PresetManager, PresetSource};
+use rebuild::{Rebuilder, TopologyWatcher};
+use shared::SHARED;
+use topology::{Controls, Tanks, Topology, Unit};
+
+use std::sync::atomic::Ordering;
 
 baseplug::model! {
     #[derive(Debug, Serialize, Deserialize)]
+    #[serde(default)]
     struct ReverbModel {
         #[model(min = 0.4, max = 0.9)]
         #[parameter(name = "g")]
@@ -31,6 +54,316 @@ baseplug::model! {
         #[model(min = 0.0, max = 1.0)]
         #[parameter(name = "glitch_enum")]
         glitch_enum: f32,
+        #[model(min = 0.0, max = 1.0)]
+        #[parameter(name = "freeze")]
+        freeze: f32,
+        #[model(min = 0.0, max = 1.0)]
+        #[parameter(name = "spectral_freeze")]
+        spectral_freeze: f32,
+        #[model(min = 0.0, max = 1.0)]
+        #[parameter(name = "spectral_blur")]
+        spectral_blur: f32,
+        #[model(min = 0.05, max = 2.0)]
+        #[parameter(name = "reverse_length")]
+        reverse_length: f32,
+        #[model(min = 0.0, max = 1.0)]
+        #[parameter(name = "reverse_sync")]
+        reverse_sync: f32,
+        #[model(min = 0.0, max = 1.0)]
+        #[parameter(name = "shimmer_amount")]
+        shimmer_amount: f32,
+        #[model(min = 0.0, max = 1.0)]
+        #[parameter(name = "shimmer_interval")]
+        shimmer_interval: f32,
+        #[model(min = -24.0, max = 24.0)]
+        #[parameter(name = "shimmer_semitones")]
+        shimmer_semitones: f32,
+        #[model(min = 0.05, max = 1.0)]
+        #[parameter(name = "shimmer_tone")]
+        shimmer_tone: f32,
+        #[model(min = 0.0, max = 1.0)]
+        #[parameter(name = "gate")]
+        gate: f32,
+        #[model(min = -60.0, max = 0.0)]
+        #[parameter(name = "gate_threshold")]
+        gate_threshold: f32,
+        #[model(min = 0.0, max = 1.0)]
+        #[parameter(name = "gate_hold")]
+        gate_hold: f32,
+        #[model(min = 0.0001, max = 0.1)]
+        #[parameter(name = "gate_attack")]
+        gate_attack: f32,
+        #[model(min = 0.001, max = 1.0)]
+        #[parameter(name = "gate_release")]
+        gate_release: f32,
+        #[model(min = 0.0, max = 1.0)]
+        #[parameter(name = "duck_amount")]
+        duck_amount: f32,
+        #[model(min = -60.0, max = 0.0)]
+        #[parameter(name = "duck_threshold")]
+        duck_threshold: f32,
+        #[model(min = 0.001, max = 0.5)]
+        #[parameter(name = "duck_attack")]
+        duck_attack: f32,
+        #[model(min = 0.01, max = 2.0)]
+        #[parameter(name = "duck_release")]
+        duck_release: f32,
+        #[model(min = 0.0, max = 1.0)]
+        #[parameter(name = "sidechain")]
+        sidechain: f32,
+        #[model(min = 0.0, max = 1.0)]
+        #[parameter(name = "freeze_trigger")]
+        freeze_trigger: f32,
+        #[model(min = 0.0, max = 1.0)]
+        #[parameter(name = "lfo_shape")]
+        lfo_shape: f32,
+        #[model(min = 0.05, max = 20.0)]
+        #[parameter(name = "lfo_rate")]
+        lfo_rate: f32,
+        #[model(min = 0.0, max = 1.0)]
+        #[parameter(name = "lfo_phase")]
+        lfo_phase: f32,
+        #[model(min = 0.0, max = 1.0)]
+        #[parameter(name = "lfo_spread")]
+        lfo_spread: f32,
+        #[model(min = 0.0, max = 1.0)]
+        #[parameter(name = "lfo_sync")]
+        lfo_sync: f32,
+        #[model(min = 0.0, max = 1.0)]
+        #[parameter(name = "lfo_division")]
+        lfo_division: f32,
+        #[model(min = 0.0, max = 1.0)]
+        #[parameter(name = "lfo_division_feel")]
+        lfo_division_feel: f32,
+        #[model(min = 0.0, max = 127.0)]
+        #[parameter(name = "midi_freeze_note")]
+        midi_freeze_note: f32,
+        #[model(min = 0.0, max = 127.0)]
+        #[parameter(name = "midi_glitch_note")]
+        midi_glitch_note: f32,
+        #[model(min = 0.0, max = 1.0)]
+        #[parameter(name = "mod_1_source")]
+        mod_1_source: f32,
+        #[model(min = 0.0, max = 255.0)]
+        #[parameter(name = "mod_1_destination")]
+        mod_1_destination: f32,
+        #[model(min = -1.0, max = 1.0)]
+        #[parameter(name = "mod_1_amount")]
+        mod_1_amount: f32,
+        #[model(min = 0.0, max = 1.0)]
+        #[parameter(name = "mod_2_source")]
+        mod_2_source: f32,
+        #[model(min = 0.0, max = 255.0)]
+        #[parameter(name = "mod_2_destination")]
+        mod_2_destination: f32,
+        #[model(min = -1.0, max = 1.0)]
+        #[parameter(name = "mod_2_amount")]
+        mod_2_amount: f32,
+        #[model(min = 0.0, max = 1.0)]
+        #[parameter(name = "mod_3_source")]
+        mod_3_source: f32,
+        #[model(min = 0.0, max = 255.0)]
+        #[parameter(name = "mod_3_destination")]
+        mod_3_destination: f32,
+        #[model(min = -1.0, max = 1.0)]
+        #[parameter(name = "mod_3_amount")]
+        mod_3_amount: f32,
+        #[model(min = 0.0, max = 1.0)]
+        #[parameter(name = "mod_4_source")]
+        mod_4_source: f32,
+        #[model(min = 0.0, max = 255.0)]
+        #[parameter(name = "mod_4_destination")]
+        mod_4_destination: f32,
+        #[model(min = -1.0, max = 1.0)]
+        #[parameter(name = "mod_4_amount")]
+        mod_4_amount: f32,
+        #[model(min = 0.0, max = 1.0)]
+        #[parameter(name = "clear_on_stop")]
+        clear_on_stop: f32,
+        #[model(min = 0.0, max = 1.0)]
+        #[parameter(name = "quality")]
+        quality: f32,
+        #[model(min = 0.01, max = 1.0)]
+        #[parameter(name = "spectral_smoothing")]
+        spectral_smoothing: f32,
+        // the cc mappings aren't host parameters, so they can't be automated and only live in the
+        // plugin's saved state
+        #[model(min = 0.0, max = 128.0)]
+        midi_1_cc: f32,
+        #[model(min = 0.0, max = 255.0)]
+        midi_1_parameter: f32,
+        #[model(min = 0.0, max = 1.0)]
+        midi_1_from: f32,
+        #[model(min = 0.0, max = 1.0)]
+        midi_1_to: f32,
+        #[model(min = 0.0, max = 128.0)]
+        midi_2_cc: f32,
+        #[model(min = 0.0, max = 255.0)]
+        midi_2_parameter: f32,
+        #[model(min = 0.0, max = 1.0)]
+        midi_2_from: f32,
+        #[model(min = 0.0, max = 1.0)]
+        midi_2_to: f32,
+        #[model(min = 0.0, max = 128.0)]
+        midi_3_cc: f32,
+        #[model(min = 0.0, max = 255.0)]
+        midi_3_parameter: f32,
+        #[model(min = 0.0, max = 1.0)]
+        midi_3_from: f32,
+        #[model(min = 0.0, max = 1.0)]
+        midi_3_to: f32,
+        #[model(min = 0.0, max = 128.0)]
+        midi_4_cc: f32,
+        #[model(min = 0.0, max = 255.0)]
+        midi_4_parameter: f32,
+        #[model(min = 0.0, max = 1.0)]
+        midi_4_from: f32,
+        #[model(min = 0.0, max = 1.0)]
+        midi_4_to: f32,
+        #[model(min = 0.0, max = 128.0)]
+        midi_5_cc: f32,
+        #[model(min = 0.0, max = 255.0)]
+        midi_5_parameter: f32,
+        #[model(min = 0.0, max = 1.0)]
+        midi_5_from: f32,
+        #[model(min = 0.0, max = 1.0)]
+        midi_5_to: f32,
+        #[model(min = 0.0, max = 128.0)]
+        midi_6_cc: f32,
+        #[model(min = 0.0, max = 255.0)]
+        midi_6_parameter: f32,
+        #[model(min = 0.0, max = 1.0)]
+        midi_6_from: f32,
+        #[model(min = 0.0, max = 1.0)]
+        midi_6_to: f32,
+        #[model(min = 0.0, max = 128.0)]
+        midi_7_cc: f32,
+        #[model(min = 0.0, max = 255.0)]
+        midi_7_parameter: f32,
+        #[model(min = 0.0, max = 1.0)]
+        midi_7_from: f32,
+        #[model(min = 0.0, max = 1.0)]
+        midi_7_to: f32,
+        #[model(min = 0.0, max = 128.0)]
+        midi_8_cc: f32,
+        #[model(min = 0.0, max = 255.0)]
+        midi_8_parameter: f32,
+        #[model(min = 0.0, max = 1.0)]
+        midi_8_from: f32,
+        #[model(min = 0.0, max = 1.0)]
+        midi_8_to: f32,
+        #[model(min = 0.0, max = 1.0)]
+        #[parameter(name = "ab_slot")]
+        ab_slot: f32,
+        #[model(min = 0.0, max = 1.0)]
+        #[parameter(name = "ab_level_match")]
+        ab_level_match: f32,
+        #[model(min = 0.0, max = 1.0)]
+        #[parameter(name = "glitch_reverse")]
+        glitch_reverse: f32,
+        // set while this instance's editor is waiting for a cc to map
+        #[model(min = 0.0, max = 1.0)]
+        midi_learn: f32,
+        // which of SHARED's instance slots this instance reports to, set by its editor
+        #[model(min = 0.0, max = 65535.0)]
+        instance: f32,
+        // whichever of a and b isn't active, kept with the plugin's state rather than as host
+        // parameters. ab_stored is set once it's been filled, then one field per sound parameter in
+        // the order of SOUND_PARAMETER_NAMES
+        #[model(min = 0.0, max = 1.0)]
+        ab_stored: f32,
+        #[model(min = 0.4, max = 0.9)]
+        ab_stored_g: f32,
+        #[model(min = 0.0, max = 1.0)]
+        ab_stored_damping: f32,
+        #[model(min = 0.0, max = 29.0)]
+        ab_stored_degrade_intensity: f32,
+        #[model(min = 0.0, max = 1.0)]
+        ab_stored_degrade_speed: f32,
+        #[model(min = 0.0, max = 1.0)]
+        ab_stored_glitch_enum: f32,
+        #[model(min = 0.0, max = 1.0)]
+        ab_stored_freeze: f32,
+        #[model(min = 0.0, max = 1.0)]
+        ab_stored_spectral_freeze: f32,
+        #[model(min = 0.0, max = 1.0)]
+        ab_stored_spectral_blur: f32,
+        #[model(min = 0.05, max = 2.0)]
+        ab_stored_reverse_length: f32,
+        #[model(min = 0.0, max = 1.0)]
+        ab_stored_reverse_sync: f32,
+        #[model(min = 0.0, max = 1.0)]
+        ab_stored_shimmer_amount: f32,
+        #[model(min = 0.0, max = 1.0)]
+        ab_stored_shimmer_interval: f32,
+        #[model(min = -24.0, max = 24.0)]
+        ab_stored_shimmer_semitones: f32,
+        #[model(min = 0.05, max = 1.0)]
+        ab_stored_shimmer_tone: f32,
+        #[model(min = 0.0, max = 1.0)]
+        ab_stored_gate: f32,
+        #[model(min = -60.0, max = 0.0)]
+        ab_stored_gate_threshold: f32,
+        #[model(min = 0.0, max = 1.0)]
+        ab_stored_gate_hold: f32,
+        #[model(min = 0.0001, max = 0.1)]
+        ab_stored_gate_attack: f32,
+        #[model(min = 0.001, max = 1.0)]
+        ab_stored_gate_release: f32,
+        #[model(min = 0.0, max = 1.0)]
+        ab_stored_duck_amount: f32,
+        #[model(min = -60.0, max = 0.0)]
+        ab_stored_duck_threshold: f32,
+        #[model(min = 0.001, max = 0.5)]
+        ab_stored_duck_attack: f32,
+        #[model(min = 0.01, max = 2.0)]
+        ab_stored_duck_release: f32,
+        #[model(min = 0.0, max = 1.0)]
+        ab_stored_sidechain: f32,
+        #[model(min = 0.0, max = 1.0)]
+        ab_stored_freeze_trigger: f32,
+        #[model(min = 0.0, max = 1.0)]
+        ab_stored_lfo_shape: f32,
+        #[model(min = 0.05, max = 20.0)]
+        ab_stored_lfo_rate: f32,
+        #[model(min = 0.0, max = 1.0)]
+        ab_stored_lfo_phase: f32,
+        #[model(min = 0.0, max = 1.0)]
+        ab_stored_lfo_spread: f32,
+        #[model(min = 0.0, max = 1.0)]
+        ab_stored_lfo_sync: f32,
+        #[model(min = 0.0, max = 1.0)]
+        ab_stored_lfo_division: f32,
+        #[model(min = 0.0, max = 1.0)]
+        ab_stored_lfo_division_feel: f32,
+        #[model(min = 0.0, max = 1.0)]
+        ab_stored_mod_1_source: f32,
+        #[model(min = 0.0, max = 255.0)]
+        ab_stored_mod_1_destination: f32,
+        #[model(min = -1.0, max = 1.0)]
+        ab_stored_mod_1_amount: f32,
+        #[model(min = 0.0, max = 1.0)]
+        ab_stored_mod_2_source: f32,
+        #[model(min = 0.0, max = 255.0)]
+        ab_stored_mod_2_destination: f32,
+        #[model(min = -1.0, max = 1.0)]
+        ab_stored_mod_2_amount: f32,
+        #[model(min = 0.0, max = 1.0)]
+        ab_stored_mod_3_source: f32,
+        #[model(min = 0.0, max = 255.0)]
+        ab_stored_mod_3_destination: f32,
+        #[model(min = -1.0, max = 1.0)]
+        ab_stored_mod_3_amount: f32,
+        #[model(min = 0.0, max = 1.0)]
+        ab_stored_mod_4_source: f32,
+        #[model(min = 0.0, max = 255.0)]
+        ab_stored_mod_4_destination: f32,
+        #[model(min = -1.0, max = 1.0)]
+        ab_stored_mod_4_amount: f32,
+        #[model(min = 0.01, max = 1.0)]
+        ab_stored_spectral_smoothing: f32,
+        #[model(min = 0.0, max = 1.0)]
+        ab_stored_glitch_reverse: f32,
     }
 }
 
@@ -42,149 +375,1615 @@ impl Default for ReverbModel {
             degrade_intensity: 0.0,
             degrade_speed: 0.0,
             glitch_enum: 0.0,
+            freeze: 0.0,
+            spectral_freeze: 0.0,
+            spectral_blur: 0.0,
+            reverse_length: 0.5,
+            reverse_sync: 0.0,
+            shimmer_amount: 0.0,
+            shimmer_interval: 0.0,
+            shimmer_semitones: 12.0,
+            shimmer_tone: 0.5,
+            gate: 0.0,
+            gate_threshold: -30.0,
+            gate_hold: 0.25,
+            gate_attack: 0.001,
+            gate_release: 0.05,
+            duck_amount: 0.0,
+            duck_threshold: -30.0,
+            duck_attack: 0.01,
+            duck_release: 0.25,
+            sidechain: 0.0,
+            freeze_trigger: 0.0,
+            lfo_shape: 0.0,
+            lfo_rate: 5.0,
+            lfo_phase: 0.0,
+            lfo_spread: 0.0,
+            lfo_sync: 0.0,
+            lfo_division: 0.5,
+            lfo_division_feel: 0.0,
+            midi_freeze_note: 60.0,
+            midi_glitch_note: 48.0,
+            mod_1_source: 0.0,
+            mod_1_destination: 0.0,
+            mod_1_amount: 0.0,
+            mod_2_source: 0.0,
+            mod_2_destination: 0.0,
+            mod_2_amount: 0.0,
+            mod_3_source: 0.0,
+            mod_3_destination: 0.0,
+            mod_3_amount: 0.0,
+            mod_4_source: 0.0,
+            mod_4_destination: 0.0,
+            mod_4_amount: 0.0,
+            clear_on_stop: 0.0,
+            quality: 0.0,
+            spectral_smoothing: 0.5,
+            // ccs bound out of the box, mod wheel (cc 1) also feeds the modulation matrix
+            midi_1_cc: 128.0,
+            midi_1_parameter: 0.0,
+            midi_1_from: 0.0,
+            midi_1_to: 1.0,
+            midi_2_cc: 128.0,
+            midi_2_parameter: 0.0,
+            midi_2_from: 0.0,
+            midi_2_to: 1.0,
+            midi_3_cc: 128.0,
+            midi_3_parameter: 0.0,
+            midi_3_from: 0.0,
+            midi_3_to: 1.0,
+            midi_4_cc: 128.0,
+            midi_4_parameter: 0.0,
+            midi_4_from: 0.0,
+            midi_4_to: 1.0,
+            midi_5_cc: 128.0,
+            midi_5_parameter: 0.0,
+            midi_5_from: 0.0,
+            midi_5_to: 1.0,
+            midi_6_cc: 128.0,
+            midi_6_parameter: 0.0,
+            midi_6_from: 0.0,
+            midi_6_to: 1.0,
+            midi_7_cc: 128.0,
+            midi_7_parameter: 0.0,
+            midi_7_from: 0.0,
+            midi_7_to: 1.0,
+            midi_8_cc: 128.0,
+            midi_8_parameter: 0.0,
+            midi_8_from: 0.0,
+            midi_8_to: 1.0,
+            ab_slot: 0.0,
+            ab_level_match: 0.0,
+            glitch_reverse: 0.0,
+            midi_learn: 0.0,
+            instance: 0.0,
+            ab_stored: 0.0,
+            ab_stored_g: 0.7,
+            ab_stored_damping: 0.0,
+            ab_stored_degrade_intensity: 0.0,
+            ab_stored_degrade_speed: 0.0,
+            ab_stored_glitch_enum: 0.0,
+            ab_stored_freeze: 0.0,
+            ab_stored_spectral_freeze: 0.0,
+            ab_stored_spectral_blur: 0.0,
+            ab_stored_reverse_length: 0.5,
+            ab_stored_reverse_sync: 0.0,
+            ab_stored_shimmer_amount: 0.0,
+            ab_stored_shimmer_interval: 0.0,
+            ab_stored_shimmer_semitones: 12.0,
+            ab_stored_shimmer_tone: 0.5,
+            ab_stored_gate: 0.0,
+            ab_stored_gate_threshold: -30.0,
+            ab_stored_gate_hold: 0.25,
+            ab_stored_gate_attack: 0.001,
+            ab_stored_gate_release: 0.05,
+            ab_stored_duck_amount: 0.0,
+            ab_stored_duck_threshold: -30.0,
+            ab_stored_duck_attack: 0.01,
+            ab_stored_duck_release: 0.25,
+            ab_stored_sidechain: 0.0,
+            ab_stored_freeze_trigger: 0.0,
+            ab_stored_lfo_shape: 0.0,
+            ab_stored_lfo_rate: 5.0,
+            ab_stored_lfo_phase: 0.0,
+            ab_stored_lfo_spread: 0.0,
+            ab_stored_lfo_sync: 0.0,
+            ab_stored_lfo_division: 0.5,
+            ab_stored_lfo_division_feel: 0.0,
+            ab_stored_mod_1_source: 0.0,
+            ab_stored_mod_1_destination: 0.0,
+            ab_stored_mod_1_amount: 0.0,
+            ab_stored_mod_2_source: 0.0,
+            ab_stored_mod_2_destination: 0.0,
+            ab_stored_mod_2_amount: 0.0,
+            ab_stored_mod_3_source: 0.0,
+            ab_stored_mod_3_destination: 0.0,
+            ab_stored_mod_3_amount: 0.0,
+            ab_stored_mod_4_source: 0.0,
+            ab_stored_mod_4_destination: 0.0,
+            ab_stored_mod_4_amount: 0.0,
+            ab_stored_spectral_smoothing: 0.5,
+            ab_stored_glitch_reverse: 0.0,
+        }
+    }
+}
+
+// every parameter in the order they're declared in the model, with their ranges
+const PARAMETERS: [(&str, f32, f32); 133] = [
+    ("g", 0.4, 0.9),
+    ("damping", 0.0, 1.0),
+    ("degrade_intensity", 0.0, 29.0),
+    ("degrade_speed", 0.0, 1.0),
+    ("glitch_enum", 0.0, 1.0),
+    ("freeze", 0.0, 1.0),
+    ("spectral_freeze", 0.0, 1.0),
+    ("spectral_blur", 0.0, 1.0),
+    ("reverse_length", 0.05, 2.0),
+    ("reverse_sync", 0.0, 1.0),
+    ("shimmer_amount", 0.0, 1.0),
+    ("shimmer_interval", 0.0, 1.0),
+    ("shimmer_semitones", -24.0, 24.0),
+    ("shimmer_tone", 0.05, 1.0),
+    ("gate", 0.0, 1.0),
+    ("gate_threshold", -60.0, 0.0),
+    ("gate_hold", 0.0, 1.0),
+    ("gate_attack", 0.0001, 0.1),
+    ("gate_release", 0.001, 1.0),
+    ("duck_amount", 0.0, 1.0),
+    ("duck_threshold", -60.0, 0.0),
+    ("duck_attack", 0.001, 0.5),
+    ("duck_release", 0.01, 2.0),
+    ("sidechain", 0.0, 1.0),
+    ("freeze_trigger", 0.0, 1.0),
+    ("lfo_shape", 0.0, 1.0),
+    ("lfo_rate", 0.05, 20.0),
+    ("lfo_phase", 0.0, 1.0),
+    ("lfo_spread", 0.0, 1.0),
+    ("lfo_sync", 0.0, 1.0),
+    ("lfo_division", 0.0, 1.0),
+    ("lfo_division_feel", 0.0, 1.0),
+    ("midi_freeze_note", 0.0, 127.0),
+    ("midi_glitch_note", 0.0, 127.0),
+    ("mod_1_source", 0.0, 1.0),
+    ("mod_1_destination", 0.0, 255.0),
+    ("mod_1_amount", -1.0, 1.0),
+    ("mod_2_source", 0.0, 1.0),
+    ("mod_2_destination", 0.0, 255.0),
+    ("mod_2_amount", -1.0, 1.0),
+    ("mod_3_source", 0.0, 1.0),
+    ("mod_3_destination", 0.0, 255.0),
+    ("mod_3_amount", -1.0, 1.0),
+    ("mod_4_source", 0.0, 1.0),
+    ("mod_4_destination", 0.0, 255.0),
+    ("mod_4_amount", -1.0, 1.0),
+    ("clear_on_stop", 0.0, 1.0),
+    ("quality", 0.0, 1.0),
+    ("spectral_smoothing", 0.01, 1.0),
+    ("midi_1_cc", 0.0, 128.0),
+    ("midi_1_parameter", 0.0, 255.0),
+    ("midi_1_from", 0.0, 1.0),
+    ("midi_1_to", 0.0, 1.0),
+    ("midi_2_cc", 0.0, 128.0),
+    ("midi_2_parameter", 0.0, 255.0),
+    ("midi_2_from", 0.0, 1.0),
+    ("midi_2_to", 0.0, 1.0),
+    ("midi_3_cc", 0.0, 128.0),
+    ("midi_3_parameter", 0.0, 255.0),
+    ("midi_3_from", 0.0, 1.0),
+    ("midi_3_to", 0.0, 1.0),
+    ("midi_4_cc", 0.0, 128.0),
+    ("midi_4_parameter", 0.0, 255.0),
+    ("midi_4_from", 0.0, 1.0),
+    ("midi_4_to", 0.0, 1.0),
+    ("midi_5_cc", 0.0, 128.0),
+    ("midi_5_parameter", 0.0, 255.0),
+    ("midi_5_from", 0.0, 1.0),
+    ("midi_5_to", 0.0, 1.0),
+    ("midi_6_cc", 0.0, 128.0),
+    ("midi_6_parameter", 0.0, 255.0),
+    ("midi_6_from", 0.0, 1.0),
+    ("midi_6_to", 0.0, 1.0),
+    ("midi_7_cc", 0.0, 128.0),
+    ("midi_7_parameter", 0.0, 255.0),
+    ("midi_7_from", 0.0, 1.0),
+    ("midi_7_to", 0.0, 1.0),
+    ("midi_8_cc", 0.0, 128.0),
+    ("midi_8_parameter", 0.0, 255.0),
+    ("midi_8_from", 0.0, 1.0),
+    ("midi_8_to", 0.0, 1.0),
+    ("ab_slot", 0.0, 1.0),
+    ("ab_level_match", 0.0, 1.0),
+    ("glitch_reverse", 0.0, 1.0),
+    ("midi_learn", 0.0, 1.0),
+    ("instance", 0.0, 65535.0),
+    ("ab_stored", 0.0, 1.0),
+    ("ab_stored_g", 0.4, 0.9),
+    ("ab_stored_damping", 0.0, 1.0),
+    ("ab_stored_degrade_intensity", 0.0, 29.0),
+    ("ab_stored_degrade_speed", 0.0, 1.0),
+    ("ab_stored_glitch_enum", 0.0, 1.0),
+    ("ab_stored_freeze", 0.0, 1.0),
+    ("ab_stored_spectral_freeze", 0.0, 1.0),
+    ("ab_stored_spectral_blur", 0.0, 1.0),
+    ("ab_stored_reverse_length", 0.05, 2.0),
+    ("ab_stored_reverse_sync", 0.0, 1.0),
+    ("ab_stored_shimmer_amount", 0.0, 1.0),
+    ("ab_stored_shimmer_interval", 0.0, 1.0),
+    ("ab_stored_shimmer_semitones", -24.0, 24.0),
+    ("ab_stored_shimmer_tone", 0.05, 1.0),
+    ("ab_stored_gate", 0.0, 1.0),
+    ("ab_stored_gate_threshold", -60.0, 0.0),
+    ("ab_stored_gate_hold", 0.0, 1.0),
+    ("ab_stored_gate_attack", 0.0001, 0.1),
+    ("ab_stored_gate_release", 0.001, 1.0),
+    ("ab_stored_duck_amount", 0.0, 1.0),
+    ("ab_stored_duck_threshold", -60.0, 0.0),
+    ("ab_stored_duck_attack", 0.001, 0.5),
+    ("ab_stored_duck_release", 0.01, 2.0),
+    ("ab_stored_sidechain", 0.0, 1.0),
+    ("ab_stored_freeze_trigger", 0.0, 1.0),
+    ("ab_stored_lfo_shape", 0.0, 1.0),
+    ("ab_stored_lfo_rate", 0.05, 20.0),
+    ("ab_stored_lfo_phase", 0.0, 1.0),
+    ("ab_stored_lfo_spread", 0.0, 1.0),
+    ("ab_stored_lfo_sync", 0.0, 1.0),
+    ("ab_stored_lfo_division", 0.0, 1.0),
+    ("ab_stored_lfo_division_feel", 0.0, 1.0),
+    ("ab_stored_mod_1_source", 0.0, 1.0),
+    ("ab_stored_mod_1_destination", 0.0, 255.0),
+    ("ab_stored_mod_1_amount", -1.0, 1.0),
+    ("ab_stored_mod_2_source", 0.0, 1.0),
+    ("ab_stored_mod_2_destination", 0.0, 255.0),
+    ("ab_stored_mod_2_amount", -1.0, 1.0),
+    ("ab_stored_mod_3_source", 0.0, 1.0),
+    ("ab_stored_mod_3_destination", 0.0, 255.0),
+    ("ab_stored_mod_3_amount", -1.0, 1.0),
+    ("ab_stored_mod_4_source", 0.0, 1.0),
+    ("ab_stored_mod_4_destination", 0.0, 255.0),
+    ("ab_stored_mod_4_amount", -1.0, 1.0),
+    ("ab_stored_spectral_smoothing", 0.01, 1.0),
+    ("ab_stored_glitch_reverse", 0.0, 1.0),
+];
+// a mod destination is stored as the index of its parameter in PARAMETERS, which only ever grows
+// at the end, so new parameters never move what a session modulates. this is the top of its range
+const MOD_DESTINATION_MAX: f32 = 255.0;
+// what presets, a/b and midi mappings cover. the midi notes, clear_on_stop and quality are left out
+// since they're how this instance is set up rather than how it sounds
+const SOUND_PARAMETER_NAMES: [&str; 46] = [
+    "g",
+    "damping",
+    "degrade_intensity",
+    "degrade_speed",
+    "glitch_enum",
+    "freeze",
+    "spectral_freeze",
+    "spectral_blur",
+    "reverse_length",
+    "reverse_sync",
+    "shimmer_amount",
+    "shimmer_interval",
+    "shimmer_semitones",
+    "shimmer_tone",
+    "gate",
+    "gate_threshold",
+    "gate_hold",
+    "gate_attack",
+    "gate_release",
+    "duck_amount",
+    "duck_threshold",
+    "duck_attack",
+    "duck_release",
+    "sidechain",
+    "freeze_trigger",
+    "lfo_shape",
+    "lfo_rate",
+    "lfo_phase",
+    "lfo_spread",
+    "lfo_sync",
+    "lfo_division",
+    "lfo_division_feel",
+    "mod_1_source",
+    "mod_1_destination",
+    "mod_1_amount",
+    "mod_2_source",
+    "mod_2_destination",
+    "mod_2_amount",
+    "mod_3_source",
+    "mod_3_destination",
+    "mod_3_amount",
+    "mod_4_source",
+    "mod_4_destination",
+    "mod_4_amount",
+    "spectral_smoothing",
+    "glitch_reverse",
+];
+// where the cc, parameter, from and to of the first midi mapping slot are
+const MIDI_SLOTS_START: usize = 49;
+// whether the inactive a/b slot has been filled, its values follow in the order of
+// SOUND_PARAMETER_NAMES
+const AB_STORED: usize = 86;
+
+fn parameter_index(name: &str) -> Option<usize> {
+    PARAMETERS.iter().position(|parameter| parameter.0 == name)
+}
+
+fn is_sound_parameter(index: usize) -> bool {
+    index < PARAMETERS.len() && SOUND_PARAMETER_NAMES.contains(&PARAMETERS[index].0)
+}
+
+// the matrix can target every sound parameter but its own settings
+fn is_mod_destination(index: usize) -> bool {
+    is_sound_parameter(index) && !PARAMETERS[index].0.starts_with("mod_")
+}
+
+// indices into PARAMETERS of the sound parameters, in the order of SOUND_PARAMETER_NAMES
+fn sound_parameters() -> Vec<usize> {
+    SOUND_PARAMETER_NAMES
+        .iter()
+        .filter_map(|name| parameter_index(name))
+        .collect()
+}
+
+// indices into PARAMETERS of the inactive a/b slot's copy of each sound parameter
+fn stored_parameters() -> Vec<usize> {
+    (0..SOUND_PARAMETER_NAMES.len())
+        .map(|index| AB_STORED + 1 + index)
+        .collect()
+}
+
+// continuous parameters get smoothed before they reach the dsp, with a time in seconds each.
+// degrade_intensity ramps linearly so the downsampling steps through each amount evenly
+const SMOOTHED_PARAMETERS: [(&str, f32, SmootherKind); 10] = [
+    ("g", 0.05, SmootherKind::OnePole),
+    ("damping", 0.02, SmootherKind::OnePole),
+    ("degrade_intensity", 0.1, SmootherKind::Linear),
+    ("degrade_speed", 0.02, SmootherKind::OnePole),
+    ("spectral_blur", 0.05, SmootherKind::OnePole),
+    ("shimmer_amount", 0.02, SmootherKind::OnePole),
+    ("shimmer_semitones", 0.05, SmootherKind::Linear),
+    ("shimmer_tone", 0.02, SmootherKind::OnePole),
+    ("duck_amount", 0.02, SmootherKind::OnePole),
+    ("lfo_rate", 0.05, SmootherKind::OnePole),
+];
+
+impl ReverbModel {
+    // the values at one sample of a process block
+    fn at(model: &ReverbModelProcess, i: usize) -> Self {
+        Self {
+            g: model.g[i],
+            damping: model.damping[i],
+            degrade_intensity: model.degrade_intensity[i],
+            degrade_speed: model.degrade_speed[i],
+            glitch_enum: model.glitch_enum[i],
+            freeze: model.freeze[i],
+            spectral_freeze: model.spectral_freeze[i],
+            spectral_blur: model.spectral_blur[i],
+            reverse_length: model.reverse_length[i],
+            reverse_sync: model.reverse_sync[i],
+            shimmer_amount: model.shimmer_amount[i],
+            shimmer_interval: model.shimmer_interval[i],
+            shimmer_semitones: model.shimmer_semitones[i],
+            shimmer_tone: model.shimmer_tone[i],
+            gate: model.gate[i],
+            gate_threshold: model.gate_threshold[i],
+            gate_hold: model.gate_hold[i],
+            gate_attack: model.gate_attack[i],
+            gate_release: model.gate_release[i],
+            duck_amount: model.duck_amount[i],
+            duck_threshold: model.duck_threshold[i],
+            duck_attack: model.duck_attack[i],
+            duck_release: model.duck_release[i],
+            sidechain: model.sidechain[i],
+            freeze_trigger: model.freeze_trigger[i],
+            lfo_shape: model.lfo_shape[i],
+            lfo_rate: model.lfo_rate[i],
+            lfo_phase: model.lfo_phase[i],
+            lfo_spread: model.lfo_spread[i],
+            lfo_sync: model.lfo_sync[i],
+            lfo_division: model.lfo_division[i],
+            lfo_division_feel: model.lfo_division_feel[i],
+            midi_freeze_note: model.midi_freeze_note[i],
+            midi_glitch_note: model.midi_glitch_note[i],
+            mod_1_source: model.mod_1_source[i],
+            mod_1_destination: model.mod_1_destination[i],
+            mod_1_amount: model.mod_1_amount[i],
+            mod_2_source: model.mod_2_source[i],
+            mod_2_destination: model.mod_2_destination[i],
+            mod_2_amount: model.mod_2_amount[i],
+            mod_3_source: model.mod_3_source[i],
+            mod_3_destination: model.mod_3_destination[i],
+            mod_3_amount: model.mod_3_amount[i],
+            mod_4_source: model.mod_4_source[i],
+            mod_4_destination: model.mod_4_destination[i],
+            mod_4_amount: model.mod_4_amount[i],
+            clear_on_stop: model.clear_on_stop[i],
+            quality: model.quality[i],
+            spectral_smoothing: model.spectral_smoothing[i],
+            midi_1_cc: model.midi_1_cc[i],
+            midi_1_parameter: model.midi_1_parameter[i],
+            midi_1_from: model.midi_1_from[i],
+            midi_1_to: model.midi_1_to[i],
+            midi_2_cc: model.midi_2_cc[i],
+            midi_2_parameter: model.midi_2_parameter[i],
+            midi_2_from: model.midi_2_from[i],
+            midi_2_to: model.midi_2_to[i],
+            midi_3_cc: model.midi_3_cc[i],
+            midi_3_parameter: model.midi_3_parameter[i],
+            midi_3_from: model.midi_3_from[i],
+            midi_3_to: model.midi_3_to[i],
+            midi_4_cc: model.midi_4_cc[i],
+            midi_4_parameter: model.midi_4_parameter[i],
+            midi_4_from: model.midi_4_from[i],
+            midi_4_to: model.midi_4_to[i],
+            midi_5_cc: model.midi_5_cc[i],
+            midi_5_parameter: model.midi_5_parameter[i],
+            midi_5_from: model.midi_5_from[i],
+            midi_5_to: model.midi_5_to[i],
+            midi_6_cc: model.midi_6_cc[i],
+            midi_6_parameter: model.midi_6_parameter[i],
+            midi_6_from: model.midi_6_from[i],
+            midi_6_to: model.midi_6_to[i],
+            midi_7_cc: model.midi_7_cc[i],
+            midi_7_parameter: model.midi_7_parameter[i],
+            midi_7_from: model.midi_7_from[i],
+            midi_7_to: model.midi_7_to[i],
+            midi_8_cc: model.midi_8_cc[i],
+            midi_8_parameter: model.midi_8_parameter[i],
+            midi_8_from: model.midi_8_from[i],
+            midi_8_to: model.midi_8_to[i],
+            ab_slot: model.ab_slot[i],
+            ab_level_match: model.ab_level_match[i],
+            glitch_reverse: model.glitch_reverse[i],
+            midi_learn: model.midi_learn[i],
+            instance: model.instance[i],
+            ab_stored: model.ab_stored[i],
+            ab_stored_g: model.ab_stored_g[i],
+            ab_stored_damping: model.ab_stored_damping[i],
+            ab_stored_degrade_intensity: model.ab_stored_degrade_intensity[i],
+            ab_stored_degrade_speed: model.ab_stored_degrade_speed[i],
+            ab_stored_glitch_enum: model.ab_stored_glitch_enum[i],
+            ab_stored_freeze: model.ab_stored_freeze[i],
+            ab_stored_spectral_freeze: model.ab_stored_spectral_freeze[i],
+            ab_stored_spectral_blur: model.ab_stored_spectral_blur[i],
+            ab_stored_reverse_length: model.ab_stored_reverse_length[i],
+            ab_stored_reverse_sync: model.ab_stored_reverse_sync[i],
+            ab_stored_shimmer_amount: model.ab_stored_shimmer_amount[i],
+            ab_stored_shimmer_interval: model.ab_stored_shimmer_interval[i],
+            ab_stored_shimmer_semitones: model.ab_stored_shimmer_semitones[i],
+            ab_stored_shimmer_tone: model.ab_stored_shimmer_tone[i],
+            ab_stored_gate: model.ab_stored_gate[i],
+            ab_stored_gate_threshold: model.ab_stored_gate_threshold[i],
+            ab_stored_gate_hold: model.ab_stored_gate_hold[i],
+            ab_stored_gate_attack: model.ab_stored_gate_attack[i],
+            ab_stored_gate_release: model.ab_stored_gate_release[i],
+            ab_stored_duck_amount: model.ab_stored_duck_amount[i],
+            ab_stored_duck_threshold: model.ab_stored_duck_threshold[i],
+            ab_stored_duck_attack: model.ab_stored_duck_attack[i],
+            ab_stored_duck_release: model.ab_stored_duck_release[i],
+            ab_stored_sidechain: model.ab_stored_sidechain[i],
+            ab_stored_freeze_trigger: model.ab_stored_freeze_trigger[i],
+            ab_stored_lfo_shape: model.ab_stored_lfo_shape[i],
+            ab_stored_lfo_rate: model.ab_stored_lfo_rate[i],
+            ab_stored_lfo_phase: model.ab_stored_lfo_phase[i],
+            ab_stored_lfo_spread: model.ab_stored_lfo_spread[i],
+            ab_stored_lfo_sync: model.ab_stored_lfo_sync[i],
+            ab_stored_lfo_division: model.ab_stored_lfo_division[i],
+            ab_stored_lfo_division_feel: model.ab_stored_lfo_division_feel[i],
+            ab_stored_mod_1_source: model.ab_stored_mod_1_source[i],
+            ab_stored_mod_1_destination: model.ab_stored_mod_1_destination[i],
+            ab_stored_mod_1_amount: model.ab_stored_mod_1_amount[i],
+            ab_stored_mod_2_source: model.ab_stored_mod_2_source[i],
+            ab_stored_mod_2_destination: model.ab_stored_mod_2_destination[i],
+            ab_stored_mod_2_amount: model.ab_stored_mod_2_amount[i],
+            ab_stored_mod_3_source: model.ab_stored_mod_3_source[i],
+            ab_stored_mod_3_destination: model.ab_stored_mod_3_destination[i],
+            ab_stored_mod_3_amount: model.ab_stored_mod_3_amount[i],
+            ab_stored_mod_4_source: model.ab_stored_mod_4_source[i],
+            ab_stored_mod_4_destination: model.ab_stored_mod_4_destination[i],
+            ab_stored_mod_4_amount: model.ab_stored_mod_4_amount[i],
+            ab_stored_spectral_smoothing: model.ab_stored_spectral_smoothing[i],
+            ab_stored_glitch_reverse: model.ab_stored_glitch_reverse[i],
+        }
+    }
+
+    // parameters by their index in PARAMETERS
+    fn value_mut(&mut self, index: usize) -> Option<&mut f32> {
+        match index {
+            0 => Some(&mut self.g),
+            1 => Some(&mut self.damping),
+            2 => Some(&mut self.degrade_intensity),
+            3 => Some(&mut self.degrade_speed),
+            4 => Some(&mut self.glitch_enum),
+            5 => Some(&mut self.freeze),
+            6 => Some(&mut self.spectral_freeze),
+            7 => Some(&mut self.spectral_blur),
+            8 => Some(&mut self.reverse_length),
+            9 => Some(&mut self.reverse_sync),
+            10 => Some(&mut self.shimmer_amount),
+            11 => Some(&mut self.shimmer_interval),
+            12 => Some(&mut self.shimmer_semitones),
+            13 => Some(&mut self.shimmer_tone),
+            14 => Some(&mut self.gate),
+            15 => Some(&mut self.gate_threshold),
+            16 => Some(&mut self.gate_hold),
+            17 => Some(&mut self.gate_attack),
+            18 => Some(&mut self.gate_release),
+            19 => Some(&mut self.duck_amount),
+            20 => Some(&mut self.duck_threshold),
+            21 => Some(&mut self.duck_attack),
+            22 => Some(&mut self.duck_release),
+            23 => Some(&mut self.sidechain),
+            24 => Some(&mut self.freeze_trigger),
+            25 => Some(&mut self.lfo_shape),
+            26 => Some(&mut self.lfo_rate),
+            27 => Some(&mut self.lfo_phase),
+            28 => Some(&mut self.lfo_spread),
+            29 => Some(&mut self.lfo_sync),
+            30 => Some(&mut self.lfo_division),
+            31 => Some(&mut self.lfo_division_feel),
+            32 => Some(&mut self.midi_freeze_note),
+            33 => Some(&mut self.midi_glitch_note),
+            34 => Some(&mut self.mod_1_source),
+            35 => Some(&mut self.mod_1_destination),
+            36 => Some(&mut self.mod_1_amount),
+            37 => Some(&mut self.mod_2_source),
+            38 => Some(&mut self.mod_2_destination),
+            39 => Some(&mut self.mod_2_amount),
+            40 => Some(&mut self.mod_3_source),
+            41 => Some(&mut self.mod_3_destination),
+            42 => Some(&mut self.mod_3_amount),
+            43 => Some(&mut self.mod_4_source),
+            44 => Some(&mut self.mod_4_destination),
+            45 => Some(&mut self.mod_4_amount),
+            46 => Some(&mut self.clear_on_stop),
+            47 => Some(&mut self.quality),
+            48 => Some(&mut self.spectral_smoothing),
+            49 => Some(&mut self.midi_1_cc),
+            50 => Some(&mut self.midi_1_parameter),
+            51 => Some(&mut self.midi_1_from),
+            52 => Some(&mut self.midi_1_to),
+            53 => Some(&mut self.midi_2_cc),
+            54 => Some(&mut self.midi_2_parameter),
+            55 => Some(&mut self.midi_2_from),
+            56 => Some(&mut self.midi_2_to),
+            57 => Some(&mut self.midi_3_cc),
+            58 => Some(&mut self.midi_3_parameter),
+            59 => Some(&mut self.midi_3_from),
+            60 => Some(&mut self.midi_3_to),
+            61 => Some(&mut self.midi_4_cc),
+            62 => Some(&mut self.midi_4_parameter),
+            63 => Some(&mut self.midi_4_from),
+            64 => Some(&mut self.midi_4_to),
+            65 => Some(&mut self.midi_5_cc),
+            66 => Some(&mut self.midi_5_parameter),
+            67 => Some(&mut self.midi_5_from),
+            68 => Some(&mut self.midi_5_to),
+            69 => Some(&mut self.midi_6_cc),
+            70 => Some(&mut self.midi_6_parameter),
+            71 => Some(&mut self.midi_6_from),
+            72 => Some(&mut self.midi_6_to),
+            73 => Some(&mut self.midi_7_cc),
+            74 => Some(&mut self.midi_7_parameter),
+            75 => Some(&mut self.midi_7_from),
+            76 => Some(&mut self.midi_7_to),
+            77 => Some(&mut self.midi_8_cc),
+            78 => Some(&mut self.midi_8_parameter),
+            79 => Some(&mut self.midi_8_from),
+            80 => Some(&mut self.midi_8_to),
+            81 => Some(&mut self.ab_slot),
+            82 => Some(&mut self.ab_level_match),
+            83 => Some(&mut self.glitch_reverse),
+            84 => Some(&mut self.midi_learn),
+            85 => Some(&mut self.instance),
+            86 => Some(&mut self.ab_stored),
+            87 => Some(&mut self.ab_stored_g),
+            88 => Some(&mut self.ab_stored_damping),
+            89 => Some(&mut self.ab_stored_degrade_intensity),
+            90 => Some(&mut self.ab_stored_degrade_speed),
+            91 => Some(&mut self.ab_stored_glitch_enum),
+            92 => Some(&mut self.ab_stored_freeze),
+            93 => Some(&mut self.ab_stored_spectral_freeze),
+            94 => Some(&mut self.ab_stored_spectral_blur),
+            95 => Some(&mut self.ab_stored_reverse_length),
+            96 => Some(&mut self.ab_stored_reverse_sync),
+            97 => Some(&mut self.ab_stored_shimmer_amount),
+            98 => Some(&mut self.ab_stored_shimmer_interval),
+            99 => Some(&mut self.ab_stored_shimmer_semitones),
+            100 => Some(&mut self.ab_stored_shimmer_tone),
+            101 => Some(&mut self.ab_stored_gate),
+            102 => Some(&mut self.ab_stored_gate_threshold),
+            103 => Some(&mut self.ab_stored_gate_hold),
+            104 => Some(&mut self.ab_stored_gate_attack),
+            105 => Some(&mut self.ab_stored_gate_release),
+            106 => Some(&mut self.ab_stored_duck_amount),
+            107 => Some(&mut self.ab_stored_duck_threshold),
+            108 => Some(&mut self.ab_stored_duck_attack),
+            109 => Some(&mut self.ab_stored_duck_release),
+            110 => Some(&mut self.ab_stored_sidechain),
+            111 => Some(&mut self.ab_stored_freeze_trigger),
+            112 => Some(&mut self.ab_stored_lfo_shape),
+            113 => Some(&mut self.ab_stored_lfo_rate),
+            114 => Some(&mut self.ab_stored_lfo_phase),
+            115 => Some(&mut self.ab_stored_lfo_spread),
+            116 => Some(&mut self.ab_stored_lfo_sync),
+            117 => Some(&mut self.ab_stored_lfo_division),
+            118 => Some(&mut self.ab_stored_lfo_division_feel),
+            119 => Some(&mut self.ab_stored_mod_1_source),
+            120 => Some(&mut self.ab_stored_mod_1_destination),
+            121 => Some(&mut self.ab_stored_mod_1_amount),
+            122 => Some(&mut self.ab_stored_mod_2_source),
+            123 => Some(&mut self.ab_stored_mod_2_destination),
+            124 => Some(&mut self.ab_stored_mod_2_amount),
+            125 => Some(&mut self.ab_stored_mod_3_source),
+            126 => Some(&mut self.ab_stored_mod_3_destination),
+            127 => Some(&mut self.ab_stored_mod_3_amount),
+            128 => Some(&mut self.ab_stored_mod_4_source),
+            129 => Some(&mut self.ab_stored_mod_4_destination),
+            130 => Some(&mut self.ab_stored_mod_4_amount),
+            131 => Some(&mut self.ab_stored_spectral_smoothing),
+            132 => Some(&mut self.ab_stored_glitch_reverse),
+            _ => None,
+        }
+    }
+
+    fn mod_slots(&self) -> [ModSlot; modulation::SLOTS] {
+        [
+            mod_slot(self.mod_1_source, self.mod_1_destination, self.mod_1_amount),
+            mod_slot(self.mod_2_source, self.mod_2_destination, self.mod_2_amount),
+            mod_slot(self.mod_3_source, self.mod_3_destination, self.mod_3_amount),
+            mod_slot(self.mod_4_source, self.mod_4_destination, self.mod_4_amount),
+        ]
+    }
+
+    fn cc_bindings(&self) -> [Option<CcBinding>; midi::SLOTS] {
+        [
+            cc_binding(
+                self.midi_1_cc,
+                self.midi_1_parameter,
+                self.midi_1_from,
+                self.midi_1_to,
+            ),
+            cc_binding(
+                self.midi_2_cc,
+                self.midi_2_parameter,
+                self.midi_2_from,
+                self.midi_2_to,
+            ),
+            cc_binding(
+                self.midi_3_cc,
+                self.midi_3_parameter,
+                self.midi_3_from,
+                self.midi_3_to,
+            ),
+            cc_binding(
+                self.midi_4_cc,
+                self.midi_4_parameter,
+                self.midi_4_from,
+                self.midi_4_to,
+            ),
+            cc_binding(
+                self.midi_5_cc,
+                self.midi_5_parameter,
+                self.midi_5_from,
+                self.midi_5_to,
+            ),
+            cc_binding(
+                self.midi_6_cc,
+                self.midi_6_parameter,
+                self.midi_6_from,
+                self.midi_6_to,
+            ),
+            cc_binding(
+                self.midi_7_cc,
+                self.midi_7_parameter,
+                self.midi_7_from,
+                self.midi_7_to,
+            ),
+            cc_binding(
+                self.midi_8_cc,
+                self.midi_8_parameter,
+                self.midi_8_from,
+                self.midi_8_to,
+            ),
+        ]
+    }
+}
+
+// the editor's copy of every parameter, in the order of PARAMETERS. a macro so the parameter type
+// doesn't have to be spelled out
+macro_rules! ui_params {
+    ($model:expr) => {
+        [
+            &mut $model.g,
+            &mut $model.damping,
+            &mut $model.degrade_intensity,
+            &mut $model.degrade_speed,
+            &mut $model.glitch_enum,
+            &mut $model.freeze,
+            &mut $model.spectral_freeze,
+            &mut $model.spectral_blur,
+            &mut $model.reverse_length,
+            &mut $model.reverse_sync,
+            &mut $model.shimmer_amount,
+            &mut $model.shimmer_interval,
+            &mut $model.shimmer_semitones,
+            &mut $model.shimmer_tone,
+            &mut $model.gate,
+            &mut $model.gate_threshold,
+            &mut $model.gate_hold,
+            &mut $model.gate_attack,
+            &mut $model.gate_release,
+            &mut $model.duck_amount,
+            &mut $model.duck_threshold,
+            &mut $model.duck_attack,
+            &mut $model.duck_release,
+            &mut $model.sidechain,
+            &mut $model.freeze_trigger,
+            &mut $model.lfo_shape,
+            &mut $model.lfo_rate,
+            &mut $model.lfo_phase,
+            &mut $model.lfo_spread,
+            &mut $model.lfo_sync,
+            &mut $model.lfo_division,
+            &mut $model.lfo_division_feel,
+            &mut $model.midi_freeze_note,
+            &mut $model.midi_glitch_note,
+            &mut $model.mod_1_source,
+            &mut $model.mod_1_destination,
+            &mut $model.mod_1_amount,
+            &mut $model.mod_2_source,
+            &mut $model.mod_2_destination,
+            &mut $model.mod_2_amount,
+            &mut $model.mod_3_source,
+            &mut $model.mod_3_destination,
+            &mut $model.mod_3_amount,
+            &mut $model.mod_4_source,
+            &mut $model.mod_4_destination,
+            &mut $model.mod_4_amount,
+            &mut $model.clear_on_stop,
+            &mut $model.quality,
+            &mut $model.spectral_smoothing,
+            &mut $model.midi_1_cc,
+            &mut $model.midi_1_parameter,
+            &mut $model.midi_1_from,
+            &mut $model.midi_1_to,
+            &mut $model.midi_2_cc,
+            &mut $model.midi_2_parameter,
+            &mut $model.midi_2_from,
+            &mut $model.midi_2_to,
+            &mut $model.midi_3_cc,
+            &mut $model.midi_3_parameter,
+            &mut $model.midi_3_from,
+            &mut $model.midi_3_to,
+            &mut $model.midi_4_cc,
+            &mut $model.midi_4_parameter,
+            &mut $model.midi_4_from,
+            &mut $model.midi_4_to,
+            &mut $model.midi_5_cc,
+            &mut $model.midi_5_parameter,
+            &mut $model.midi_5_from,
+            &mut $model.midi_5_to,
+            &mut $model.midi_6_cc,
+            &mut $model.midi_6_parameter,
+            &mut $model.midi_6_from,
+            &mut $model.midi_6_to,
+            &mut $model.midi_7_cc,
+            &mut $model.midi_7_parameter,
+            &mut $model.midi_7_from,
+            &mut $model.midi_7_to,
+            &mut $model.midi_8_cc,
+            &mut $model.midi_8_parameter,
+            &mut $model.midi_8_from,
+            &mut $model.midi_8_to,
+            &mut $model.ab_slot,
+            &mut $model.ab_level_match,
+            &mut $model.glitch_reverse,
+            &mut $model.midi_learn,
+            &mut $model.instance,
+            &mut $model.ab_stored,
+            &mut $model.ab_stored_g,
+            &mut $model.ab_stored_damping,
+            &mut $model.ab_stored_degrade_intensity,
+            &mut $model.ab_stored_degrade_speed,
+            &mut $model.ab_stored_glitch_enum,
+            &mut $model.ab_stored_freeze,
+            &mut $model.ab_stored_spectral_freeze,
+            &mut $model.ab_stored_spectral_blur,
+            &mut $model.ab_stored_reverse_length,
+            &mut $model.ab_stored_reverse_sync,
+            &mut $model.ab_stored_shimmer_amount,
+            &mut $model.ab_stored_shimmer_interval,
+            &mut $model.ab_stored_shimmer_semitones,
+            &mut $model.ab_stored_shimmer_tone,
+            &mut $model.ab_stored_gate,
+            &mut $model.ab_stored_gate_threshold,
+            &mut $model.ab_stored_gate_hold,
+            &mut $model.ab_stored_gate_attack,
+            &mut $model.ab_stored_gate_release,
+            &mut $model.ab_stored_duck_amount,
+            &mut $model.ab_stored_duck_threshold,
+            &mut $model.ab_stored_duck_attack,
+            &mut $model.ab_stored_duck_release,
+            &mut $model.ab_stored_sidechain,
+            &mut $model.ab_stored_freeze_trigger,
+            &mut $model.ab_stored_lfo_shape,
+            &mut $model.ab_stored_lfo_rate,
+            &mut $model.ab_stored_lfo_phase,
+            &mut $model.ab_stored_lfo_spread,
+            &mut $model.ab_stored_lfo_sync,
+            &mut $model.ab_stored_lfo_division,
+            &mut $model.ab_stored_lfo_division_feel,
+            &mut $model.ab_stored_mod_1_source,
+            &mut $model.ab_stored_mod_1_destination,
+            &mut $model.ab_stored_mod_1_amount,
+            &mut $model.ab_stored_mod_2_source,
+            &mut $model.ab_stored_mod_2_destination,
+            &mut $model.ab_stored_mod_2_amount,
+            &mut $model.ab_stored_mod_3_source,
+            &mut $model.ab_stored_mod_3_destination,
+            &mut $model.ab_stored_mod_3_amount,
+            &mut $model.ab_stored_mod_4_source,
+            &mut $model.ab_stored_mod_4_destination,
+            &mut $model.ab_stored_mod_4_amount,
+            &mut $model.ab_stored_spectral_smoothing,
+            &mut $model.ab_stored_glitch_reverse,
+        ]
+    };
+}
+
+// plain values of every parameter with nothing changed, in the order of PARAMETERS
+fn default_values() -> Vec<f32> {
+    let mut model = ReverbModel::default();
+    (0..PARAMETERS.len())
+        .map(|index| model.value_mut(index).map_or(0.0, |value| *value))
+        .collect()
+}
+
+// None for an empty slot, or one pointing at something that isn't a sound parameter
+fn cc_binding(cc: f32, parameter: f32, from: f32, to: f32) -> Option<CcBinding> {
+    let parameter = parameter.round() as usize;
+    if cc.round() >= midi::NO_CC || !is_sound_parameter(parameter) {
+        return None;
+    }
+    Some(CcBinding {
+        cc: cc.round() as u8,
+        parameter,
+        from,
+        to,
+    })
+}
+
+// a slot pointing at something that can't be modulated does nothing
+fn mod_slot(source: f32, destination: f32, amount: f32) -> ModSlot {
+    let destination = destination.round() as usize;
+    ModSlot {
+        source: if is_mod_destination(destination) {
+            modulation::SOURCES[step_index(source, modulation::SOURCES.len())]
+        } else {
+            modulation::ModSource::None
+        },
+        destination,
+        amount,
+    }
+}
+
+// the glitch notes pick these, from midi_glitch_note upwards
+const GLITCH_NOTE_MODES: [GlitchEnum; 4] = [
+    GlitchEnum::Not,
+    GlitchEnum::Indeed,
+    GlitchEnum::Lfo,
+    GlitchEnum::Reverse,
+];
+
+// glitch_enum's whole range is split between the modes older sessions saved (at 0.0, 0.31 and
+// 0.71), so modes added since then get a parameter of their own and take priority
+#[derive(PartialEq, Clone, Copy)]
+enum GlitchEnum {
+    Not,
+    Indeed,
+    Reverse,
+    Lfo,
+}
+
+impl GlitchEnum {
+    fn from_values(glitch: f32, reverse: f32) -> Self {
+        if reverse > 0.5 {
+            GlitchEnum::Reverse
+        } else if glitch > 0.7 {
+            GlitchEnum::Lfo
+        } else if glitch > 0.3 {
+            GlitchEnum::Indeed
+        } else {
+            GlitchEnum::Not
+        }
+    }
+
+    // glitch_enum's value for the legacy modes, None for the ones with their own parameter
+    fn value(self) -> Option<f32> {
+        match self {
+            GlitchEnum::Not => Some(0.0),
+            GlitchEnum::Indeed => Some(0.31),
+            GlitchEnum::Lfo => Some(0.71),
+            GlitchEnum::Reverse => None,
+        }
+    }
+}
+
+// how long freeze takes to fade in and out, in seconds
+const FREEZE_FADE_TIME: f32 = 0.05;
+
+#[derive(PartialEq, Clone, Copy)]
+enum ShimmerInterval {
+    OctaveUp,
+    FifthUp,
+    OctaveDown,
+    Custom,
+}
+
+impl ShimmerInterval {
+    fn from_value(value: f32) -> Self {
+        if value > 0.83 {
+            ShimmerInterval::Custom
+        } else if value > 0.5 {
+            ShimmerInterval::OctaveDown
+        } else if value > 0.16 {
+            ShimmerInterval::FifthUp
+        } else {
+            ShimmerInterval::OctaveUp
+        }
+    }
+
+    fn value(self) -> f32 {
+        match self {
+            ShimmerInterval::OctaveUp => 0.0,
+            ShimmerInterval::FifthUp => 0.33,
+            ShimmerInterval::OctaveDown => 0.66,
+            ShimmerInterval::Custom => 1.0,
         }
     }
 }
 
+// lfo_shape is spread evenly over these, legacy first so old sessions keep it
+const LFO_SHAPES: [LfoShape; 7] = [
+    LfoShape::Legacy,
+    LfoShape::Sine,
+    LfoShape::Triangle,
+    LfoShape::Saw,
+    LfoShape::Square,
+    LfoShape::SampleAndHold,
+    LfoShape::SmoothRandom,
+];
+const LFO_SHAPE_NAMES: [&str; 7] = [
+    "legacy",
+    "sine",
+    "triangle",
+    "saw",
+    "square",
+    "s&h",
+    "smooth random",
+];
+
+// note lengths in quarter notes, lfo_division is spread evenly over these
+const NOTE_DIVISIONS: [f32; 9] = [16.0, 8.0, 4.0, 2.0, 1.0, 0.5, 0.25, 0.125, 0.0625];
+const NOTE_DIVISION_NAMES: [&str; 9] = [
+    "4 bars", "2 bars", "1 bar", "1/2", "1/4", "1/8", "1/16", "1/32", "1/64",
+];
+// straight, dotted and triplet, lfo_division_feel is spread evenly over these
+const DIVISION_FEELS: [f32; 3] = [1.0, 1.5, 2.0 / 3.0];
+const DIVISION_FEEL_NAMES: [&str; 3] = ["straight", "dotted", "triplet"];
+
+fn step_index(value: f32, count: usize) -> usize {
+    ((value * (count - 1) as f32).round() as usize).min(count - 1)
+}
+
+// length of one lfo cycle in quarter notes
+fn division_beats(division: f32, feel: f32) -> f64 {
+    (NOTE_DIVISIONS[step_index(division, NOTE_DIVISIONS.len())]
+        * DIVISION_FEELS[step_index(feel, DIVISION_FEELS.len())]) as f64
+}
+
+// longest reverse window, in seconds
+const MAX_REVERSE_TIME: f32 = 2.0;
+// crossfade between reversed windows, in seconds
+const REVERSE_FADE_TIME: f32 = 0.01;
+// oversampling of the nonlinear stages, from the quality parameter
+const QUALITY_FACTORS: [usize; 4] = [1, 2, 4, 8];
+const QUALITY_NAMES: [&str; 4] = ["1x", "2x", "4x", "8x"];
+
+// how long the wet signal takes to come back after the tank was cleared, in seconds
+const SAFETY_FADE_TIME: f32 = 0.05;
+// crossfade from the old tanks to ones built from an edited topology file, in seconds
+const TOPOLOGY_FADE_TIME: f32 = 0.1;
+// how much signal the a/b levels average over, in seconds
+const AB_LEVEL_TIME: f32 = 3.0;
+// blocks with an input quieter than this (mean square, about -80 dB) don't count towards the a/b
+// levels
+const AB_SILENCE: f32 = 1e-8;
+
 struct Reverb {
-    allpass_one_l: Allpass,
-    allpass_two_l: Allpass,
-    allpass_three_l: Allpass,
-    delay_one_l: DegradedDelay,
-    delay_two_l: DegradedDelay,
-    delay_three_l: DegradedDelay,
-    delay_four_l: DegradedDelay,
-    allpass_one_r: Allpass,
-    allpass_two_r: Allpass,
-    allpass_three_r: Allpass,
-    delay_one_r: DegradedDelay,
-    delay_two_r: DegradedDelay,
-    delay_three_r: DegradedDelay,
-    delay_four_r: DegradedDelay,
+    tank_l: Unit, //built from the topology
+    tank_r: Unit,
     lfo: Lfo,
+    freeze: f32, //current position of the freeze crossfade
+    spectral_l: SpectralFreeze,
+    spectral_r: SpectralFreeze,
+    reverser_l: Reverser,
+    reverser_r: Reverser,
+    input_follower: EnvelopeFollower,
+    gate: Gate,
+    ducker: Ducker,
+    was_playing: bool,
+    mod_sources: ModSources,
+    mod_offsets: [f32; PARAMETERS.len()],
+    mod_random: Lfo,
+    midi: MidiState,
+    smoothers: Vec<(usize, Smoother)>, //index into the parameter table and its smoother
+    sound_parameters: Vec<usize>,
+    output_oversampler_l: Oversampler, //around the output clamp
+    output_oversampler_r: Oversampler,
+    safety_fade: f32, //wet gain, 0.0 right after the tank was cleared for blowing up
+    tank_resets: usize, //how many times that's happened, for the editor
+    rebuilder: Option<Rebuilder<Box<Reverb>>>, //for sample rate changes, boxed on the worker
+    requested_rate: f32, //rate a rebuild has been asked for, 0.0 if none
+    watcher: Option<TopologyWatcher>,
+    fading_tanks: Option<Tanks>, //the tanks from before the topology file changed, fading out
+    tank_fade: f32,              //0.0 right after new tanks came in, 1.0 once the old ones are gone
+    ab_output: [f32; 2],         //mean square of the output while each of a and b was active
+    ab_input: [f32; 2],          //and of the input over the same blocks
+    ab_gain: Smoother,
     sample_rate: f32,
 }
 
+impl Reverb {
+    // everything that depends on the sample rate. the rebuilder and watcher are left for `new` to
+    // start
+    fn build(sample_rate: f32) -> Self {
+        // a topology file that doesn't parse leaves the default layout in place
+        let topology = match Topology::load() {
+            Ok(topology) => {
+                topology::report_error(None);
+                topology
+            }
+            Err(error) => {
+                topology::report_error(Some(error));
+                Topology::default()
+            }
+        };
+        let tanks = topology.build(sample_rate);
+
+        Self {
+            tank_l: tanks.left,
+            tank_r: tanks.right,
+            lfo: Lfo::new(sample_rate, 5.0),
+            freeze: 0.0,
+            spectral_l: SpectralFreeze::new(sample_rate, 1),
+            spectral_r: SpectralFreeze::new(sample_rate, 2),
+            reverser_l: Reverser::new(
+                (MAX_REVERSE_TIME * sample_rate) as usize,
+                (REVERSE_FADE_TIME * sample_rate) as usize,
+            ),
+            reverser_r: Reverser::new(
+                (MAX_REVERSE_TIME * sample_rate) as usize,
+                (REVERSE_FADE_TIME * sample_rate) as usize,
+            ),
+            input_follower: EnvelopeFollower::new(sample_rate, 0.001, 0.02),
+            gate: Gate::new(sample_rate),
+            ducker: Ducker::new(sample_rate),
+            was_playing: false,
+            mod_sources: ModSources::default(),
+            mod_offsets: [0.0; PARAMETERS.len()],
+            mod_random: {
+                let mut random = Lfo::new(sample_rate, 1.0);
+                random.set_shape(LfoShape::SmoothRandom);
+                random
+            },
+            midi: MidiState::new(PARAMETERS.len()),
+            smoothers: SMOOTHED_PARAMETERS
+                .iter()
+                .filter_map(|(name, time, kind)| {
+                    parameter_index(name)
+                        .map(|index| (index, Smoother::new(sample_rate, *time, *kind)))
+                })
+                .collect(),
+            sound_parameters: sound_parameters(),
+            output_oversampler_l: Oversampler::new(),
+            output_oversampler_r: Oversampler::new(),
+            safety_fade: 1.0,
+            tank_resets: 0,
+            rebuilder: None,
+            requested_rate: 0.0,
+            watcher: None,
+            fading_tanks: None,
+            tank_fade: 1.0,
+            ab_output: [0.0; 2],
+            ab_input: [0.0; 2],
+            ab_gain: Smoother::new(sample_rate, 0.05, SmootherKind::OnePole),
+            sample_rate,
+        }
+    }
+
+    // swaps in a rebuilt copy of everything once the host has changed rate, keeping the state that
+    // doesn't depend on it. until the new one arrives this keeps running at the old rate
+    fn follow_sample_rate(&mut self, sample_rate: f32) {
+        let mut rebuilder = match self.rebuilder.take() {
+            Some(rebuilder) => rebuilder,
+            None => return,
+        };
+
+        if let Some(mut rebuilt) = rebuilder.take() {
+            if rebuilt.sample_rate == sample_rate {
+                std::mem::swap(&mut rebuilt.midi, &mut self.midi);
+                rebuilt.was_playing = self.was_playing;
+                rebuilt.ab_output = self.ab_output;
+                rebuilt.ab_input = self.ab_input;
+                rebuilt.tank_resets = self.tank_resets;
+                // fade the wet signal in, same as after the tank is cleared
+                rebuilt.safety_fade = 0.0;
+                rebuilt.watcher = self.watcher.take();
+                if let Some(watcher) = &mut rebuilt.watcher {
+                    watcher.set_sample_rate(sample_rate);
+                }
+                std::mem::swap(self, &mut *rebuilt);
+            }
+            rebuilder.retire(rebuilt);
+            self.requested_rate = 0.0;
+        }
+
+        if sample_rate != self.sample_rate
+            && sample_rate != self.requested_rate
+            && rebuilder.request(sample_rate)
+        {
+            self.requested_rate = sample_rate;
+        }
+        self.rebuilder = Some(rebuilder);
+    }
+
+    // starts crossfading to tanks built from an edited topology file. anything that arrives while
+    // a crossfade is still going waits for the next block
+    fn follow_topology(&mut self) {
+        let watcher = match &mut self.watcher {
+            Some(watcher) => watcher,
+            None => return,
+        };
+        if self.fading_tanks.is_some() {
+            return;
+        }
+
+        if let Some(mut tanks) = watcher.take() {
+            // built before a sample rate change, the rebuild will have read the file anyway
+            if tanks.sample_rate != self.sample_rate {
+                watcher.retire(tanks);
+                return;
+            }
+            std::mem::swap(&mut tanks.left, &mut self.tank_l);
+            std::mem::swap(&mut tanks.right, &mut self.tank_r);
+            self.fading_tanks = Some(tanks);
+            self.tank_fade = 0.0;
+        }
+    }
+
+    fn finish_topology_fade(&mut self) {
+        self.tank_fade = 1.0;
+        if let (Some(tanks), Some(watcher)) = (self.fading_tanks.take(), &mut self.watcher) {
+            watcher.retire(tanks);
+        }
+    }
+
+    // back to how it was when it was created, minus the parameters. called when the transport stops
+    // if clear_on_stop is set. TODO: call it when the host resumes the plugin too, once baseplug
+    // passes suspend and resume on
+    fn reset(&mut self) {
+        self.clear_tank();
+        self.freeze = 0.0;
+        self.output_oversampler_l.reset();
+        self.output_oversampler_r.reset();
+        self.lfo.reset();
+        self.mod_random.reset();
+        self.mod_sources = ModSources::default();
+        self.mod_offsets = [0.0; PARAMETERS.len()];
+        for (_, smoother) in self.smoothers.iter_mut() {
+            smoother.reset();
+        }
+        self.safety_fade = 1.0;
+    }
+
+    // empties every delay line and filter in the wet path, and the key's followers since a nan in
+    // the input would otherwise stay in them
+    fn clear_tank(&mut self) {
+        self.finish_topology_fade();
+        self.tank_l.reset();
+        self.tank_r.reset();
+        self.spectral_l.reset();
+        self.spectral_r.reset();
+        self.reverser_l.reset();
+        self.reverser_r.reset();
+        self.input_follower.reset();
+        self.gate.reset();
+        self.ducker.reset();
+    }
+}
+
 impl Plugin for Reverb {
     const NAME: &'static str = "Reverb";
     const PRODUCT: &'static str = "PISSYWISSY";
     const VENDOR: &'static str = "audiodog301";
 
-    const INPUT_CHANNELS: usize = 2;
+    // the last two are the sidechain
+    const INPUT_CHANNELS: usize = 4;
     const OUTPUT_CHANNELS: usize = 2;
 
     type Model = ReverbModel;
 
     #[inline]
     fn new(sample_rate: f32, _model: &ReverbModel) -> Self {
-        Self {
-            allpass_one_l: Allpass::new(sample_rate, 4410, 0.7),
-            allpass_two_l: Allpass::new(sample_rate, 2999, -0.7),
-            allpass_three_l: Allpass::new(sample_rate, 2646, 0.7),
-            delay_one_l: DegradedDelay::new(sample_rate, 1323, 0.7),
-            delay_two_l: DegradedDelay::new(sample_rate, 1499, 0.7),
-            delay_three_l: DegradedDelay::new(sample_rate, 1676, 0.7),
-            delay_four_l: DegradedDelay::new(sample_rate, 1852, 0.7),
-            allpass_one_r: Allpass::new(sample_rate, 4410, 0.7),
-            allpass_two_r: Allpass::new(sample_rate, 2999, -0.7),
-            allpass_three_r: Allpass::new(sample_rate, 2646, 0.7),
-            delay_one_r: DegradedDelay::new(sample_rate, 1323, 0.7),
-            delay_two_r: DegradedDelay::new(sample_rate, 1499, 0.7),
-            delay_three_r: DegradedDelay::new(sample_rate, 1676, 0.7),
-            delay_four_r: DegradedDelay::new(sample_rate, 1852, 0.7),
-            lfo: Lfo::new(5.0),
-            sample_rate,
+        let mut reverb = Self::build(sample_rate);
+        reverb.rebuilder = Some(Rebuilder::new(|sample_rate| {
+            Box::new(Self::build(sample_rate))
+        }));
+        // only worth polling for someone working on their own topology.json
+        if storage::topology_modified().is_some() {
+            reverb.watcher = Some(TopologyWatcher::new(sample_rate));
         }
+        reverb
     }
 
     #[inline]
     fn process(&mut self, model: &ReverbModelProcess, ctx: &mut ProcessContext<Self>) {
+        let _denormal_guard = DenormalGuard::new();
+
+        if ctx.sample_rate != self.sample_rate || self.requested_rate != 0.0 {
+            self.follow_sample_rate(ctx.sample_rate);
+        }
+
         let input = &ctx.inputs[0].buffers;
         let output = &mut ctx.outputs[0].buffers;
+        let sidechain_present = input.len() >= 4;
+
+        let transport = &ctx.musical_time;
+        if transport.is_playing && !self.was_playing {
+            self.lfo.reset();
+        }
+        // so the tail of the last take doesn't ring into the next one
+        if !transport.is_playing && self.was_playing && model.clear_on_stop[0] > 0.5 {
+            self.reset();
+        }
+        self.was_playing = transport.is_playing;
+        // quarter notes per sample
+        let beats_per_sample = transport.bpm / 60.0 / self.sample_rate as f64;
+
+        self.follow_topology();
+
+        let oversampling = QUALITY_FACTORS[step_index(model.quality[0], QUALITY_FACTORS.len())];
+        self.tank_l.set_oversampling(oversampling);
+        self.tank_r.set_oversampling(oversampling);
+        if let Some(tanks) = &mut self.fading_tanks {
+            tanks.left.set_oversampling(oversampling);
+            tanks.right.set_oversampling(oversampling);
+        }
+        self.output_oversampler_l.set_factor(oversampling);
+        self.output_oversampler_r.set_factor(oversampling);
+
+        // a and b are compared by how much louder each makes its input, so it doesn't matter what
+        // was playing while each was active. level matching only ever turns the louder of them
+        // down to the quieter one, so it can't push the output past the clamp
+        let ab_slot = if model.ab_slot[0] > 0.5 { 1 } else { 0 };
+        let gain = |slot: usize| {
+            if self.ab_input[slot] > 0.0 {
+                self.ab_output[slot] / self.ab_input[slot]
+            } else {
+                0.0
+            }
+        };
+        let (active_gain, other_gain) = (gain(ab_slot), gain(1 - ab_slot));
+        let ab_target = if model.ab_level_match[0] > 0.5 && active_gain > 0.0 && other_gain > 0.0 {
+            (other_gain / active_gain).sqrt().min(1.0)
+        } else {
+            1.0
+        };
+        let mut output_power = 0.0;
+        let mut input_power = 0.0;
+
+        // the matrix itself isn't modulated, so its settings at the start of the block will do
+        let block_start = ReverbModel::at(model, 0);
+        self.midi.release_unbound(&block_start.cc_bindings());
+        let mod_slots = block_start.mod_slots();
+        modulation::evaluate(
+            &mod_slots,
+            &self.mod_sources,
+            &PARAMETERS,
+            &mut self.mod_offsets,
+        );
 
         for i in 0..ctx.nframes {
-            let mut g = model.g[i];
+            let mut params = ReverbModel::at(model, i);
+            for (index, value) in self.midi.overrides.iter().enumerate() {
+                if let (Some(value), Some(param)) = (value, params.value_mut(index)) {
+                    let (_, min, max) = PARAMETERS[index];
+                    *param = min + (value.value * (max - min));
+                }
+            }
+            for (index, offset) in self.mod_offsets.iter().enumerate() {
+                if *offset != 0.0 {
+                    let (_, min, max) = PARAMETERS[index];
+                    if let Some(value) = params.value_mut(index) {
+                        *value = (*value + offset).clamp(min, max);
+                    }
+                }
+            }
+
+            for (index, smoother) in self.smoothers.iter_mut() {
+                if let Some(value) = params.value_mut(*index) {
+                    *value = smoother.process_sample(*value);
+                }
+            }
+
+            let glitch = match self.midi.glitch {
+                Some(note) => GLITCH_NOTE_MODES[note],
+                None => GlitchEnum::from_values(params.glitch_enum, params.glitch_reverse),
+            };
+            self.lfo
+                .set_shape(LFO_SHAPES[step_index(params.lfo_shape, LFO_SHAPES.len())]);
+            if params.lfo_sync > 0.5 && transport.bpm > 0.0 {
+                let cycle = division_beats(params.lfo_division, params.lfo_division_feel);
+                self.lfo.set_freq((transport.bpm / 60.0 / cycle) as f32);
+                if transport.is_playing {
+                    // lock to the song position so the cycle lines up with the bar
+                    let beat = transport.beat + (i as f64 * beats_per_sample);
+                    self.lfo.set_phase((beat / cycle).fract() as f32);
+                }
+            } else {
+                self.lfo.set_freq(params.lfo_rate);
+            }
+            self.lfo.set_phase_offset(params.lfo_phase);
+            self.lfo.set_spread(params.lfo_spread);
+
+            let mut g_l = params.g;
+            let mut g_r = params.g;
             let mut thresh = 1.0;
-            if model.glitch_enum[i] > 0.7 {
-                g += self.lfo.next_sample(self.sample_rate) * 0.1;
+            let (lfo_l, lfo_r) = self.lfo.next_stereo();
+            self.mod_sources.lfo = lfo_l;
+            self.mod_sources.lfo_right = lfo_r;
+            self.mod_random.set_freq(params.lfo_rate);
+            self.mod_sources.random = self.mod_random.next_sample();
+            self.mod_sources.mod_wheel = self.midi.mod_wheel;
+            self.mod_sources.velocity = self.midi.velocity;
+            if glitch == GlitchEnum::Lfo {
+                g_l += lfo_l * 0.1;
+                g_r += lfo_r * 0.1;
                 thresh = 0.25
-            } else if model.glitch_enum[i] > 0.3 {
-                g = 1.0
+            } else if glitch == GlitchEnum::Indeed {
+                g_l = 1.0;
+                g_r = 1.0;
+            }
+
+            let semitones = match ShimmerInterval::from_value(params.shimmer_interval) {
+                ShimmerInterval::OctaveUp => 12.0,
+                ShimmerInterval::FifthUp => 7.0,
+                ShimmerInterval::OctaveDown => -12.0,
+                ShimmerInterval::Custom => params.shimmer_semitones,
+            };
+
+            // the sidechain parameter picks the key, the main input is only used in its place when
+            // the host gives us no sidechain channels at all
+            let key = if params.sidechain > 0.5 && sidechain_present {
+                input[2][i].abs().max(input[3][i].abs())
+            } else {
+                input[0][i].abs().max(input[1][i].abs())
+            };
+            // the followers are recursive, one nan from the host would leave them stuck
+            let key = if key.is_finite() { key } else { 0.0 };
+            let key_level = self.input_follower.process_sample(key);
+            self.mod_sources.envelope = key_level.min(1.0);
+            self.gate
+                .set_threshold(10f32.powf(params.gate_threshold / 20.0));
+            self.gate.set_hold(params.gate_hold);
+            self.gate.set_attack(params.gate_attack);
+            self.gate.set_release(params.gate_release);
+            let gate_gain = self.gate.process_sample(key_level);
+
+            // the midi freeze note sets the strength from its velocity
+            let freeze_target =
+                if params.freeze > 0.5 || (params.freeze_trigger > 0.5 && self.gate.is_open()) {
+                    1.0
+                } else {
+                    self.midi.freeze.unwrap_or(0.0)
+                };
+            let freeze_step = 1.0 / (FREEZE_FADE_TIME * self.sample_rate);
+            if self.freeze < freeze_target {
+                self.freeze = (self.freeze + freeze_step).min(freeze_target);
+            } else if self.freeze > freeze_target {
+                self.freeze = (self.freeze - freeze_step).max(freeze_target);
+            }
+
+            let controls_l = Controls {
+                g: g_l,
+                damping: params.damping,
+                degrade_amount: params.degrade_intensity as i32,
+                degrade_ratio: params.degrade_speed,
+                freeze: self.freeze,
+                shimmer: params.shimmer_amount,
+                semitones,
+                tone: params.shimmer_tone,
+            };
+            let controls_r = Controls {
+                g: g_r,
+                ..controls_l
+            };
+            self.tank_l.apply(&controls_l);
+            self.tank_r.apply(&controls_r);
+
+            let wet_l = self.tank_l.process_sample(input[0][i]) * params.g;
+            let wet_r = self.tank_r.process_sample(input[0][i]) * params.g;
+
+            // equal power, the old and new tails aren't correlated
+            let (wet_l, wet_r) = match &mut self.fading_tanks {
+                Some(tanks) => {
+                    tanks.left.apply(&controls_l);
+                    tanks.right.apply(&controls_r);
+                    let old_l = tanks.left.process_sample(input[0][i]) * params.g;
+                    let old_r = tanks.right.process_sample(input[0][i]) * params.g;
+                    let angle = self.tank_fade * std::f32::consts::FRAC_PI_2;
+                    (
+                        (wet_l * angle.sin()) + (old_l * angle.cos()),
+                        (wet_r * angle.sin()) + (old_r * angle.cos()),
+                    )
+                }
+                None => (wet_l, wet_r),
+            };
+            if self.fading_tanks.is_some() {
+                self.tank_fade += 1.0 / (TOPOLOGY_FADE_TIME * self.sample_rate);
+                if self.tank_fade >= 1.0 {
+                    self.finish_topology_fade();
+                }
+            }
+
+            let mut reverse_length = params.reverse_length * self.sample_rate;
+            if params.reverse_sync > 0.5 && ctx.musical_time.bpm > 0.0 {
+                // snap to whole sixteenth notes
+                let sixteenth = (15.0 / ctx.musical_time.bpm as f32) * self.sample_rate;
+                reverse_length = (reverse_length / sixteenth).round().max(1.0) * sixteenth;
+            }
+            self.reverser_l.set_length(reverse_length as usize);
+            self.reverser_r.set_length(reverse_length as usize);
+            let reversed_l = self.reverser_l.process_sample(wet_l);
+            let reversed_r = self.reverser_r.process_sample(wet_r);
+
+            let (wet_l, wet_r) = match glitch {
+                GlitchEnum::Reverse => (reversed_l, reversed_r),
+                _ => (wet_l, wet_r),
+            };
+
+            self.spectral_l.set_frozen(params.spectral_freeze > 0.5);
+            self.spectral_r.set_frozen(params.spectral_freeze > 0.5);
+            self.spectral_l.set_blur(params.spectral_blur);
+            self.spectral_r.set_blur(params.spectral_blur);
+            self.spectral_l.set_smoothing(params.spectral_smoothing);
+            self.spectral_r.set_smoothing(params.spectral_smoothing);
+            let wet_l = self.spectral_l.process_sample(wet_l);
+            let wet_r = self.spectral_r.process_sample(wet_r);
+
+            let (wet_l, wet_r) = if params.gate > 0.5 {
+                (wet_l * gate_gain, wet_r * gate_gain)
+            } else {
+                (wet_l, wet_r)
+            };
+
+            self.ducker.set_amount(params.duck_amount);
+            self.ducker.set_threshold(params.duck_threshold);
+            self.ducker.set_attack(params.duck_attack);
+            self.ducker.set_release(params.duck_release);
+            let duck_gain = self.ducker.process_sample(key);
+            let wet_l = wet_l * duck_gain;
+            let wet_r = wet_r * duck_gain;
+
+            // something in the tank went non-finite, start it over and fade back in rather than
+            // letting it ring out nans forever
+            let (wet_l, wet_r) = if wet_l.is_finite() && wet_r.is_finite() {
+                (wet_l * self.safety_fade, wet_r * self.safety_fade)
+            } else {
+                self.clear_tank();
+                self.safety_fade = 0.0;
+                self.tank_resets += 1;
+                (0.0, 0.0)
+            };
+            self.safety_fade =
+                (self.safety_fade + (1.0 / (SAFETY_FADE_TIME * self.sample_rate))).min(1.0);
+
+            let out_l = self
+                .output_oversampler_l
+                .process_sample(wet_l + input[0][i], |sample| sample.clamp(-thresh, thresh));
+            let out_r = self
+                .output_oversampler_r
+                .process_sample(wet_r + input[1][i], |sample| sample.clamp(-thresh, thresh));
+            // the dry path can only carry what the host gave us, but don't pass that on either
+            output[0][i] = if out_l.is_finite() {
+                out_l
+            } else {
+                self.output_oversampler_l.reset();
+                0.0
+            };
+            output[1][i] = if out_r.is_finite() {
+                out_r
+            } else {
+                self.output_oversampler_r.reset();
+                0.0
+            };
+
+            output_power += (output[0][i] * output[0][i]) + (output[1][i] * output[1][i]);
+            input_power += (input[0][i] * input[0][i]) + (input[1][i] * input[1][i]);
+            let ab_gain = self.ab_gain.process_sample(ab_target);
+            output[0][i] *= ab_gain;
+            output[1][i] *= ab_gain;
+        }
+
+        // measured before the level matching so it doesn't chase its own correction. the output
+        // and input always take in the same blocks, so their ratio is the slot's gain
+        let samples = (2 * ctx.nframes).max(1) as f32;
+        let (output_power, input_power) = (output_power / samples, input_power / samples);
+        if input_power > AB_SILENCE && output_power.is_finite() {
+            let (output_level, input_level) =
+                (&mut self.ab_output[ab_slot], &mut self.ab_input[ab_slot]);
+            if *input_level == 0.0 {
+                *output_level = output_power;
+                *input_level = input_power;
+            } else {
+                let coefficient =
+                    1.0 - (-(ctx.nframes as f32) / (AB_LEVEL_TIME * self.sample_rate)).exp();
+                *output_level += (output_power - *output_level) * coefficient;
+                *input_level += (input_power - *input_level) * coefficient;
+            }
+        }
+
+        // the host can't be told about what a cc changed, so the editor shows it instead
+        let id = model.instance[0].round() as usize;
+        if let Some(instance) = SHARED.instance(id) {
+            instance.claim(id);
+            instance
+                .tank_resets
+                .store(self.tank_resets, Ordering::Relaxed);
+            for (slot, binding) in block_start.cc_bindings().iter().enumerate() {
+                let value = binding.and_then(|binding| self.midi.overrides[binding.parameter]);
+                instance.set_override(slot, value.map(|value| value.value));
+            }
+        }
+
+        // parameters a cc took over go back to the host once it moves them
+        if ctx.nframes > 0 {
+            let mut block_end = ReverbModel::at(model, ctx.nframes - 1);
+            for index in self.sound_parameters.iter().copied() {
+                let (_, min, max) = PARAMETERS[index];
+                if let Some(value) = block_end.value_mut(index) {
+                    self.midi.follow_host(index, (*value - min) / (max - min));
+                }
+            }
+        }
+    }
+}
+
+impl MidiReceiver for Reverb {
+    fn midi_input(&mut self, model: &ReverbModelProcess, data: [u8; 3]) {
+        let control = self.midi.handle(
+            data,
+            model.midi_freeze_note[0] as u8,
+            model.midi_glitch_note[0] as u8,
+            GLITCH_NOTE_MODES.len(),
+        );
+
+        if let Some((cc, value)) = control {
+            // the editor does the learning, since only it can change the mappings. only the
+            // instance whose editor is learning says what it got, so other instances' ccs don't
+            // get bound
+            if model.midi_learn[0] > 0.5 {
+                let count = SHARED.last_cc.load(Ordering::Relaxed) >> 8;
+                SHARED
+                    .last_cc
+                    .store(((count + 1) << 8) | cc as usize, Ordering::Relaxed);
             }
 
-            self.allpass_one_l.set_g(g);
-            self.allpass_two_l.set_g(-g);
-            self.allpass_three_l.set_g(g);
-
-            self.delay_one_l.set_feedback(g);
-            self.delay_two_l.set_feedback(g);
-            self.delay_three_l.set_feedback(g);
-            self.delay_four_l.set_feedback(g);
-
-            self.delay_one_l.set_a(1.0 - model.damping[i]);
-            self.delay_two_l.set_a(1.0 - model.damping[i]);
-            self.delay_three_l.set_a(1.0 - model.damping[i]);
-            self.delay_four_l.set_a(1.0 - model.damping[i]);
-
-            self.delay_one_l.set_amt(model.degrade_intensity[i] as i32);
-            self.delay_two_l.set_amt(model.degrade_intensity[i] as i32);
-            self.delay_three_l
-                .set_amt(model.degrade_intensity[i] as i32);
-            self.delay_four_l.set_amt(model.degrade_intensity[i] as i32);
-
-            self.delay_one_l.set_ratio(model.degrade_speed[i]);
-            self.delay_two_l.set_ratio(model.degrade_speed[i]);
-            self.delay_three_l.set_ratio(model.degrade_speed[i]);
-            self.delay_four_l.set_ratio(model.degrade_speed[i]);
-
-            self.allpass_one_r.set_g(g);
-            self.allpass_two_r.set_g(g);
-            self.allpass_three_r.set_g(g);
-
-            self.delay_one_r.set_feedback(g);
-            self.delay_two_r.set_feedback(g);
-            self.delay_three_r.set_feedback(g);
-            self.delay_four_r.set_feedback(g);
-
-            self.delay_one_r.set_a(1.0 - model.damping[i]);
-            self.delay_two_r.set_a(1.0 - model.damping[i]);
-            self.delay_three_r.set_a(1.0 - model.damping[i]);
-            self.delay_four_r.set_a(1.0 - model.damping[i]);
-
-            self.delay_one_r.set_amt(model.degrade_intensity[i] as i32);
-            self.delay_two_r.set_amt(model.degrade_intensity[i] as i32);
-            self.delay_three_r
-                .set_amt(model.degrade_intensity[i] as i32);
-            self.delay_four_r.set_amt(model.degrade_intensity[i] as i32);
-
-            self.delay_one_r.set_ratio(model.degrade_speed[i]);
-            self.delay_two_r.set_ratio(model.degrade_speed[i]);
-            self.delay_three_r.set_ratio(model.degrade_speed[i]);
-            self.delay_four_r.set_ratio(model.degrade_speed[i]);
-
-            let delays_summed_l = (self.delay_one_l.process_sample(input[0][i])
-                + self.delay_two_l.process_sample(input[0][i])
-                + self.delay_three_l.process_sample(input[0][i])
-                + self.delay_four_l.process_sample(input[0][i]))
-                / 2.0;
-            let delays_summed_r = (self.delay_one_r.process_sample(input[0][i])
-                + self.delay_two_r.process_sample(input[0][i])
-                + self.delay_three_r.process_sample(input[0][i])
-                + self.delay_four_r.process_sample(input[0][i]))
-                / 2.0;
-            output[0][i] = (((self.allpass_three_l.process_sample(
-                self.allpass_two_l
-                    .process_sample(self.allpass_one_l.process_sample(delays_summed_l)),
-            )) * model.g[i])
-                + input[0][i])
-                .clamp(-thresh, thresh);
-
-            output[1][i] = (((self.allpass_three_r.process_sample(
-                self.allpass_two_r
-                    .process_sample(self.allpass_one_r.process_sample(delays_summed_r)),
-            )) * model.g[i])
-                + input[1][i])
-                .clamp(-thresh, thresh);
+            let bindings = ReverbModel::at(model, 0).cc_bindings();
+            for binding in bindings.iter().flatten().filter(|binding| binding.cc == cc) {
+                self.midi.apply_binding(binding, value);
+            }
         }
     }
 }
@@ -193,7 +1992,7 @@ impl baseplug::PluginUI for Reverb {
     type Handle = ();
 
     fn ui_size() -> (i16, i16) {
-        (300, 200)
+        (620, 800)
     }
 
     fn ui_open(
@@ -228,47 +2027,117 @@ impl baseplug::PluginUI for Reverb {
                     *value_text = format!("{:.1} {}", param.unit_value(), param.unit_label());
                 };
 
+                // a preset sets everything at once, so redo every text rather than wait on the host
+                let refresh_values = std::mem::take(&mut state.refresh_values);
                 let update_value_text = |value_text: &mut String, param: &UIFloatParam<_, _>| {
-                    if param.updated_by_host() {
+                    if refresh_values || param.updated_by_host() {
                         format_value(value_text, param)
                     }
                 };
 
+                state.follow_learning();
+                let bindings = state.plain_model().cc_bindings();
+                let overrides = state.overrides();
+                let learning = state.learning.map(|(index, _)| index);
+                let learn = std::cell::Cell::new(None);
+
+                // right-clicking a slider midi learns its parameter, the next cc that moves gets bound
                 let param_slider =
                     |ui: &mut egui::Ui,
                      label: &str,
+                     parameter: &str,
                      value_text: &mut String,
                      param: &mut UIFloatParam<_, _>| {
-                        ui.label(label);
+                        let index =
+                            parameter_index(parameter).filter(|index| is_sound_parameter(*index));
+                        let bound = bindings.iter().enumerate().find_map(|(slot, binding)| {
+                            binding
+                                .filter(|binding| Some(binding.parameter) == index)
+                                .map(|binding| (binding, overrides[slot]))
+                        });
+                        if index.is_some() && learning == index {
+                            ui.label(format!("{} (move a controller)", label));
+                        } else {
+                            match bound {
+                                // the slider shows the host's value, which the cc is overriding
+                                Some((binding, Some(value))) => {
+                                    let (_, min, max) = PARAMETERS[binding.parameter];
+                                    ui.label(format!(
+                                        "{} (cc {}, holding {:.2})",
+                                        label,
+                                        binding.cc,
+                                        min + (value * (max - min))
+                                    ))
+                                }
+                                Some((binding, None)) => {
+                                    ui.label(format!("{} (cc {})", label, binding.cc))
+                                }
+                                None => ui.label(label),
+                            };
+                        }
 
                         // Use the normalized value of the param so we can take advantage of baseplug's value curves.
                         //
                         // You could opt to use your own custom widget if you wish, as long as it can operate with
                         // a normalized range from [0.0, 1.0].
                         let mut normal = param.normalized();
-                        if ui.add(egui::Slider::new(&mut normal, 0.0..=1.0)).changed() {
+                        let response = ui
+                            .add(egui::Slider::new(&mut normal, 0.0..=1.0))
+                            .on_hover_text("right-click to midi learn");
+                        if response.changed() {
                             param.set_from_normalized(normal);
                             format_value(value_text, param);
                             ui.add_space(5.0);
                         };
+                        if response.secondary_clicked() {
+                            learn.set(index);
+                        }
                     };
 
-                #[derive(PartialEq)]
-                enum GlitchEnum {
-                    Not,
-                    Indeed,
-                    Lfo,
-                }
+                // steps through a parameter that picks one of a list of options
+                let step_selector =
+                    |ui: &mut egui::Ui, names: &[&str], param: &mut UIFloatParam<_, _>| {
+                        let last = names.len() - 1;
+                        let step = step_index(param.normalized(), names.len());
+                        ui.horizontal(|ui| {
+                            if ui.small_button("<").clicked() && step > 0 {
+                                param.set_from_normalized((step - 1) as f32 / last as f32);
+                            }
+                            ui.label(names[step]);
+                            if ui.small_button(">").clicked() && step < last {
+                                param.set_from_normalized((step + 1) as f32 / last as f32);
+                            }
+                        });
+                    };
 
-                let mut glitch_state;
+                // steps through the parameters the matrix can target, by their index
+                let destinations: Vec<usize> = (0..PARAMETERS.len())
+                    .filter(|index| is_mod_destination(*index))
+                    .collect();
+                let destination_selector = |ui: &mut egui::Ui, param: &mut UIFloatParam<_, _>| {
+                    let current = (param.normalized() * MOD_DESTINATION_MAX).round() as usize;
+                    let step = destinations
+                        .iter()
+                        .position(|index| *index == current)
+                        .unwrap_or(0);
+                    let mut set = |step: usize| {
+                        param.set_from_normalized(destinations[step] as f32 / MOD_DESTINATION_MAX)
+                    };
+                    ui.horizontal(|ui| {
+                        if ui.small_button("<").clicked() && step > 0 {
+                            set(step - 1);
+                        }
+                        ui.label(PARAMETERS[destinations[step]].0);
+                        if ui.small_button(">").clicked() && step + 1 < destinations.len() {
+                            set(step + 1);
+                        }
+                    });
+                };
 
-                if state.model.glitch_enum.normalized() > 0.7 {
-                    glitch_state = GlitchEnum::Lfo;
-                } else if state.model.glitch_enum.normalized() > 0.3 {
-                    glitch_state = GlitchEnum::Indeed;
-                } else {
-                    glitch_state = GlitchEnum::Not;
-                }
+                let mut glitch_state = GlitchEnum::from_values(
+                    state.model.glitch_enum.normalized(),
+                    state.model.glitch_reverse.normalized(),
+                );
                 // Sync text values if there was automation.
                 update_value_text(&mut state.g_value, &state.model.g);
                 update_value_text(&mut state.damping_value, &state.model.damping);
@@ -277,31 +2146,226 @@ impl baseplug::PluginUI for Reverb {
                     &state.model.degrade_intensity,
                 );
                 update_value_text(&mut state.degrade_speed_value, &state.model.degrade_speed);
-
-                egui::CentralPanel::default().show(&egui_ctx, |ui| {
-                    ui.horizontal(|ui| {
+                update_value_text(&mut state.spectral_blur_value, &state.model.spectral_blur);
+                update_value_text(
+                    &mut state.spectral_smoothing_value,
+                    &state.model.spectral_smoothing,
+                );
+                update_value_text(&mut state.reverse_length_value, &state.model.reverse_length);
+                update_value_text(&mut state.shimmer_amount_value, &state.model.shimmer_amount);
+                update_value_text(
+                    &mut state.shimmer_semitones_value,
+                    &state.model.shimmer_semitones,
+                );
+                update_value_text(&mut state.shimmer_tone_value, &state.model.shimmer_tone);
+                update_value_text(&mut state.gate_threshold_value, &state.model.gate_threshold);
+                update_value_text(&mut state.gate_hold_value, &state.model.gate_hold);
+                update_value_text(&mut state.gate_attack_value, &state.model.gate_attack);
+                update_value_text(&mut state.gate_release_value, &state.model.gate_release);
+                update_value_text(&mut state.duck_amount_value, &state.model.duck_amount);
+                update_value_text(&mut state.duck_threshold_value, &state.model.duck_threshold);
+                update_value_text(&mut state.duck_attack_value, &state.model.duck_attack);
+                update_value_text(&mut state.duck_release_value, &state.model.duck_release);
+                update_value_text(&mut state.lfo_rate_value, &state.model.lfo_rate);
+                update_value_text(&mut state.lfo_phase_value, &state.model.lfo_phase);
+                update_value_text(&mut state.lfo_spread_value, &state.model.lfo_spread);
+                update_value_text(
+                    &mut state.midi_freeze_note_value,
+                    &state.model.midi_freeze_note,
+                );
+                update_value_text(
+                    &mut state.midi_glitch_note_value,
+                    &state.model.midi_glitch_note,
+                );
+                update_value_text(&mut state.mod_1_amount_value, &state.model.mod_1_amount);
+                update_value_text(&mut state.mod_2_amount_value, &state.model.mod_2_amount);
+                update_value_text(&mut state.mod_3_amount_value, &state.model.mod_3_amount);
+                update_value_text(&mut state.mod_4_amount_value, &state.model.mod_4_amount);
+
+                egui::CentralPanel::default().show(&egui_ctx, |ui| {
+                    let tank_resets = state.tank_resets();
+                    if tank_resets > state.tank_resets_seen {
+                        ui.horizontal(|ui| {
+                            ui.colored_label(
+                                egui::Color32::RED,
+                                "the reverb blew up (nan or inf) and was cleared",
+                            );
+                            if ui.small_button("ok").clicked() {
+                                state.tank_resets_seen = tank_resets;
+                            }
+                        });
+                        ui.separator();
+                    }
+                    if let Some(error) = SHARED
+                        .topology_error
+                        .lock()
+                        .ok()
+                        .and_then(|error| error.clone())
+                    {
+                        ui.colored_label(
+                            egui::Color32::RED,
+                            format!("topology.json didn't load: {}", error),
+                        );
+                        ui.separator();
+                    }
+
+                    let modified = state.preset_modified();
+                    ui.horizontal(|ui| {
+                        if ui.small_button("<").clicked() {
+                            state.step_preset(false);
+                        }
+                        match state
+                            .preset
+                            .and_then(|index| state.presets.presets.get(index))
+                        {
+                            Some(entry) => ui.label(entry.preset.name.as_str()),
+                            None => ui.label("no preset"),
+                        };
+                        if ui.small_button(">").clicked() {
+                            state.step_preset(true);
+                        }
+                        if modified {
+                            ui.colored_label(egui::Color32::YELLOW, "modified");
+                        }
+                    });
+                    ui.horizontal(|ui| {
+                        if ui.selectable_label(state.ab_slot == 0, "A").clicked() {
+                            state.select_slot(0);
+                        }
+                        if ui.selectable_label(state.ab_slot == 1, "B").clicked() {
+                            state.select_slot(1);
+                        }
+                        if ui.small_button("A to B").clicked() {
+                            state.copy_slot(0);
+                        }
+                        if ui.small_button("B to A").clicked() {
+                            state.copy_slot(1);
+                        }
+                        let mut level_match = state.model.ab_level_match.normalized() > 0.5;
+                        if ui
+                            .checkbox(&mut level_match, "level match")
+                            .on_hover_text("turn whichever of a and b is louder down to match")
+                            .changed()
+                        {
+                            state
+                                .model
+                                .ab_level_match
+                                .set_from_normalized(if level_match { 1.0 } else { 0.0 });
+                        }
+                    });
+                    ui.collapsing("presets", |ui| {
+                        ui.horizontal(|ui| {
+                            ui.label("search");
+                            ui.text_edit_singleline(&mut state.preset_search);
+                        });
+                        ui.horizontal_wrapped(|ui| {
+                            let everything = state.preset_tag.is_none() && !state.favourites_only;
+                            if ui.selectable_label(everything, "all").clicked() {
+                                state.preset_tag = None;
+                                state.favourites_only = false;
+                            }
+                            if ui
+                                .selectable_label(state.favourites_only, "favourites")
+                                .clicked()
+                            {
+                                state.favourites_only = !state.favourites_only;
+                            }
+                            for tag in state.presets.tags() {
+                                let selected = state.preset_tag.as_ref() == Some(&tag);
+                                if ui.selectable_label(selected, tag.as_str()).clicked() {
+                                    state.preset_tag = if selected { None } else { Some(tag) };
+                                }
+                            }
+                        });
+
+                        let visible = state.visible_presets();
+                        egui::ScrollArea::from_max_height(160.0).show(ui, |ui| {
+                            if visible.is_empty() {
+                                ui.label("no presets match");
+                            }
+                            for index in visible {
+                                let entry = &state.presets.presets[index];
+                                let name = entry.preset.name.clone();
+                                let label = match entry.source {
+                                    PresetSource::Factory => name.clone(),
+                                    PresetSource::User(_) => format!("{} (user)", name),
+                                };
+                                let star = if state.presets.is_favourite(&name) {
+                                    "★"
+                                } else {
+                                    "☆"
+                                };
+                                ui.horizontal(|ui| {
+                                    if ui.small_button(star).clicked() {
+                                        if let Err(error) = state.presets.toggle_favourite(&name) {
+                                            state.preset_error = Some(format!(
+                                                "couldn't save the favourites: {}",
+                                                error
+                                            ));
+                                        }
+                                    }
+                                    if ui
+                                        .selectable_label(state.preset == Some(index), label)
+                                        .clicked()
+                                    {
+                                        state.load_preset(index);
+                                    }
+                                });
+                            }
+                        });
+
+                        ui.horizontal(|ui| {
+                            ui.text_edit_singleline(&mut state.preset_name);
+                            if ui.button("save as").clicked() {
+                                state.save_preset();
+                            }
+                            if state.user_preset_loaded() && ui.button("delete").clicked() {
+                                state.delete_preset();
+                            }
+                            if ui
+                                .button("rescan")
+                                .on_hover_text("pick up preset files added or edited elsewhere")
+                                .clicked()
+                            {
+                                state.reload_presets();
+                            }
+                        });
+                        if let Some(error) = &state.preset_error {
+                            ui.colored_label(egui::Color32::RED, error.as_str());
+                        }
+                        for error in state.presets.errors.iter() {
+                            ui.colored_label(egui::Color32::RED, error.as_str());
+                        }
+                    });
+                    ui.separator();
+
+                    ui.horizontal(|ui| {
                         ui.vertical(|ui| {
                             param_slider(
                                 ui,
                                 "sort of length",
+                                "g",
                                 &mut state.g_value,
                                 &mut state.model.g,
                             );
                             param_slider(
                                 ui,
                                 "damping",
+                                "damping",
                                 &mut state.damping_value,
                                 &mut state.model.damping,
                             );
                             param_slider(
                                 ui,
                                 "degradation intensity",
+                                "degrade_intensity",
                                 &mut state.degrade_intensity_value,
                                 &mut state.model.degrade_intensity,
                             );
                             param_slider(
                                 ui,
                                 "degradation speed",
+                                "degrade_speed",
                                 &mut state.degrade_speed_value,
                                 &mut state.model.degrade_speed,
                             );
@@ -309,60 +2373,411 @@ impl baseplug::PluginUI for Reverb {
                         ui.separator();
                         ui.vertical(|ui| {
                             ui.label("glitch mode");
-                            if ui
+                            let mut glitch_changed = false;
+                            glitch_changed |= ui
                                 .radio_value(&mut glitch_state, GlitchEnum::Not, "Off")
-                                .changed()
-                            {
-                                match glitch_state {
-                                    GlitchEnum::Not => {
-                                        state.model.glitch_enum.set_from_normalized(0.0)
-                                    }
-                                    GlitchEnum::Indeed => {
-                                        state.model.glitch_enum.set_from_normalized(0.31)
-                                    }
-                                    GlitchEnum::Lfo => {
-                                        state.model.glitch_enum.set_from_normalized(0.71)
+                                .changed();
+                            glitch_changed |= ui
+                                .radio_value(&mut glitch_state, GlitchEnum::Indeed, "On")
+                                .changed();
+                            glitch_changed |= ui
+                                .radio_value(&mut glitch_state, GlitchEnum::Lfo, "Wacky")
+                                .changed();
+                            glitch_changed |= ui
+                                .radio_value(&mut glitch_state, GlitchEnum::Reverse, "Reverse")
+                                .changed();
+                            if glitch_changed {
+                                match glitch_state.value() {
+                                    Some(value) => {
+                                        state.model.glitch_enum.set_from_normalized(value);
+                                        state.model.glitch_reverse.set_from_normalized(0.0);
                                     }
+                                    None => state.model.glitch_reverse.set_from_normalized(1.0),
                                 }
                             }
+                            param_slider(
+                                ui,
+                                "reverse length",
+                                "reverse_length",
+                                &mut state.reverse_length_value,
+                                &mut state.model.reverse_length,
+                            );
+                            let mut reverse_synced = state.model.reverse_sync.normalized() > 0.5;
+                            if ui.checkbox(&mut reverse_synced, "sync to tempo").changed() {
+                                state
+                                    .model
+                                    .reverse_sync
+                                    .set_from_normalized(if reverse_synced { 1.0 } else { 0.0 });
+                            }
+                            ui.separator();
+                            ui.label("wacky lfo");
+                            step_selector(ui, &LFO_SHAPE_NAMES, &mut state.model.lfo_shape);
+                            let mut lfo_synced = state.model.lfo_sync.normalized() > 0.5;
+                            if ui.checkbox(&mut lfo_synced, "sync lfo to tempo").changed() {
+                                state.model.lfo_sync.set_from_normalized(if lfo_synced {
+                                    1.0
+                                } else {
+                                    0.0
+                                });
+                            }
+                            if lfo_synced {
+                                step_selector(
+                                    ui,
+                                    &NOTE_DIVISION_NAMES,
+                                    &mut state.model.lfo_division,
+                                );
+                                let feel = step_index(
+                                    state.model.lfo_division_feel.normalized(),
+                                    DIVISION_FEELS.len(),
+                                );
+                                ui.horizontal(|ui| {
+                                    for (i, name) in DIVISION_FEEL_NAMES.iter().enumerate() {
+                                        if ui.selectable_label(feel == i, *name).clicked() {
+                                            state.model.lfo_division_feel.set_from_normalized(
+                                                i as f32 / (DIVISION_FEELS.len() - 1) as f32,
+                                            );
+                                        }
+                                    }
+                                });
+                            } else {
+                                param_slider(
+                                    ui,
+                                    "lfo rate",
+                                    "lfo_rate",
+                                    &mut state.lfo_rate_value,
+                                    &mut state.model.lfo_rate,
+                                );
+                            }
+                            param_slider(
+                                ui,
+                                "lfo phase",
+                                "lfo_phase",
+                                &mut state.lfo_phase_value,
+                                &mut state.model.lfo_phase,
+                            );
+                            param_slider(
+                                ui,
+                                "lfo stereo spread",
+                                "lfo_spread",
+                                &mut state.lfo_spread_value,
+                                &mut state.model.lfo_spread,
+                            );
+                            ui.separator();
+                            let mut frozen = state.model.freeze.normalized() > 0.5;
+                            if ui.checkbox(&mut frozen, "freeze").changed() {
+                                state.model.freeze.set_from_normalized(if frozen {
+                                    1.0
+                                } else {
+                                    0.0
+                                });
+                            }
+                            let mut spectral_frozen =
+                                state.model.spectral_freeze.normalized() > 0.5;
                             if ui
-                                .radio_value(&mut glitch_state, GlitchEnum::Indeed, "On")
+                                .checkbox(&mut spectral_frozen, "spectral freeze")
                                 .changed()
                             {
-                                match glitch_state {
-                                    GlitchEnum::Not => {
-                                        state.model.glitch_enum.set_from_normalized(0.0)
-                                    }
-                                    GlitchEnum::Indeed => {
-                                        state.model.glitch_enum.set_from_normalized(0.31)
-                                    }
-                                    GlitchEnum::Lfo => {
-                                        state.model.glitch_enum.set_from_normalized(0.71)
-                                    }
-                                }
+                                state
+                                    .model
+                                    .spectral_freeze
+                                    .set_from_normalized(if spectral_frozen { 1.0 } else { 0.0 });
+                            }
+                            param_slider(
+                                ui,
+                                "spectral blur",
+                                "spectral_blur",
+                                &mut state.spectral_blur_value,
+                                &mut state.model.spectral_blur,
+                            );
+                            param_slider(
+                                ui,
+                                "spectral smoothing",
+                                "spectral_smoothing",
+                                &mut state.spectral_smoothing_value,
+                                &mut state.model.spectral_smoothing,
+                            );
+                            ui.separator();
+                            ui.label("(rev5)");
+                        });
+                        ui.separator();
+                        ui.vertical(|ui| {
+                            param_slider(
+                                ui,
+                                "shimmer",
+                                "shimmer_amount",
+                                &mut state.shimmer_amount_value,
+                                &mut state.model.shimmer_amount,
+                            );
+                            let mut interval = ShimmerInterval::from_value(
+                                state.model.shimmer_interval.normalized(),
+                            );
+                            let mut interval_changed = false;
+                            interval_changed |= ui
+                                .radio_value(&mut interval, ShimmerInterval::OctaveUp, "+12")
+                                .changed();
+                            interval_changed |= ui
+                                .radio_value(&mut interval, ShimmerInterval::FifthUp, "+7")
+                                .changed();
+                            interval_changed |= ui
+                                .radio_value(&mut interval, ShimmerInterval::OctaveDown, "-12")
+                                .changed();
+                            interval_changed |= ui
+                                .radio_value(&mut interval, ShimmerInterval::Custom, "custom")
+                                .changed();
+                            if interval_changed {
+                                state
+                                    .model
+                                    .shimmer_interval
+                                    .set_from_normalized(interval.value());
+                            }
+                            param_slider(
+                                ui,
+                                "custom semitones",
+                                "shimmer_semitones",
+                                &mut state.shimmer_semitones_value,
+                                &mut state.model.shimmer_semitones,
+                            );
+                            param_slider(
+                                ui,
+                                "shimmer tone",
+                                "shimmer_tone",
+                                &mut state.shimmer_tone_value,
+                                &mut state.model.shimmer_tone,
+                            );
+                            ui.separator();
+                            let mut gated = state.model.gate.normalized() > 0.5;
+                            if ui.checkbox(&mut gated, "gate").changed() {
+                                state
+                                    .model
+                                    .gate
+                                    .set_from_normalized(if gated { 1.0 } else { 0.0 });
+                            }
+                            param_slider(
+                                ui,
+                                "gate threshold",
+                                "gate_threshold",
+                                &mut state.gate_threshold_value,
+                                &mut state.model.gate_threshold,
+                            );
+                            param_slider(
+                                ui,
+                                "gate hold",
+                                "gate_hold",
+                                &mut state.gate_hold_value,
+                                &mut state.model.gate_hold,
+                            );
+                            param_slider(
+                                ui,
+                                "gate attack",
+                                "gate_attack",
+                                &mut state.gate_attack_value,
+                                &mut state.model.gate_attack,
+                            );
+                            param_slider(
+                                ui,
+                                "gate release",
+                                "gate_release",
+                                &mut state.gate_release_value,
+                                &mut state.model.gate_release,
+                            );
+                        });
+                        ui.separator();
+                        ui.vertical(|ui| {
+                            param_slider(
+                                ui,
+                                "ducking",
+                                "duck_amount",
+                                &mut state.duck_amount_value,
+                                &mut state.model.duck_amount,
+                            );
+                            param_slider(
+                                ui,
+                                "duck threshold",
+                                "duck_threshold",
+                                &mut state.duck_threshold_value,
+                                &mut state.model.duck_threshold,
+                            );
+                            param_slider(
+                                ui,
+                                "duck attack",
+                                "duck_attack",
+                                &mut state.duck_attack_value,
+                                &mut state.model.duck_attack,
+                            );
+                            param_slider(
+                                ui,
+                                "duck release",
+                                "duck_release",
+                                &mut state.duck_release_value,
+                                &mut state.model.duck_release,
+                            );
+                            ui.separator();
+                            let mut sidechained = state.model.sidechain.normalized() > 0.5;
+                            if ui
+                                .checkbox(&mut sidechained, "key from sidechain")
+                                .on_hover_text(
+                                    "uses the main input if the host has no sidechain to give",
+                                )
+                                .changed()
+                            {
+                                state.model.sidechain.set_from_normalized(if sidechained {
+                                    1.0
+                                } else {
+                                    0.0
+                                });
                             }
+                            let mut triggered = state.model.freeze_trigger.normalized() > 0.5;
                             if ui
-                                .radio_value(&mut glitch_state, GlitchEnum::Lfo, "Wacky")
+                                .checkbox(&mut triggered, "freeze while gate is open")
                                 .changed()
                             {
-                                match glitch_state {
-                                    GlitchEnum::Not => {
-                                        state.model.glitch_enum.set_from_normalized(0.0)
-                                    }
-                                    GlitchEnum::Indeed => {
-                                        state.model.glitch_enum.set_from_normalized(0.31)
-                                    }
-                                    GlitchEnum::Lfo => {
-                                        state.model.glitch_enum.set_from_normalized(0.71)
-                                    }
-                                }
+                                state
+                                    .model
+                                    .freeze_trigger
+                                    .set_from_normalized(if triggered { 1.0 } else { 0.0 });
+                            }
+                            ui.label("oversampling");
+                            step_selector(ui, &QUALITY_NAMES, &mut state.model.quality);
+                            let mut clear_on_stop = state.model.clear_on_stop.normalized() > 0.5;
+                            if ui
+                                .checkbox(&mut clear_on_stop, "clear on transport stop")
+                                .changed()
+                            {
+                                state
+                                    .model
+                                    .clear_on_stop
+                                    .set_from_normalized(if clear_on_stop { 1.0 } else { 0.0 });
                             }
                             ui.separator();
-                            ui.label("(rev5)");
+                            param_slider(
+                                ui,
+                                "midi freeze note (toggles)",
+                                "midi_freeze_note",
+                                &mut state.midi_freeze_note_value,
+                                &mut state.model.midi_freeze_note,
+                            );
+                            param_slider(
+                                ui,
+                                "midi glitch notes from",
+                                "midi_glitch_note",
+                                &mut state.midi_glitch_note_value,
+                                &mut state.model.midi_glitch_note,
+                            );
                         });
                     });
+                    ui.separator();
+                    ui.label("modulation");
+                    ui.horizontal(|ui| {
+                        ui.label("1");
+                        step_selector(ui, &modulation::SOURCE_NAMES, &mut state.model.mod_1_source);
+                        ui.label("to");
+                        destination_selector(ui, &mut state.model.mod_1_destination);
+                        param_slider(
+                            ui,
+                            "by",
+                            "mod_1_amount",
+                            &mut state.mod_1_amount_value,
+                            &mut state.model.mod_1_amount,
+                        );
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("2");
+                        step_selector(ui, &modulation::SOURCE_NAMES, &mut state.model.mod_2_source);
+                        ui.label("to");
+                        destination_selector(ui, &mut state.model.mod_2_destination);
+                        param_slider(
+                            ui,
+                            "by",
+                            "mod_2_amount",
+                            &mut state.mod_2_amount_value,
+                            &mut state.model.mod_2_amount,
+                        );
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("3");
+                        step_selector(ui, &modulation::SOURCE_NAMES, &mut state.model.mod_3_source);
+                        ui.label("to");
+                        destination_selector(ui, &mut state.model.mod_3_destination);
+                        param_slider(
+                            ui,
+                            "by",
+                            "mod_3_amount",
+                            &mut state.mod_3_amount_value,
+                            &mut state.model.mod_3_amount,
+                        );
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("4");
+                        step_selector(ui, &modulation::SOURCE_NAMES, &mut state.model.mod_4_source);
+                        ui.label("to");
+                        destination_selector(ui, &mut state.model.mod_4_destination);
+                        param_slider(
+                            ui,
+                            "by",
+                            "mod_4_amount",
+                            &mut state.mod_4_amount_value,
+                            &mut state.model.mod_4_amount,
+                        );
+                    });
+
+                    ui.separator();
+                    ui.collapsing("midi mappings", |ui| {
+                        for (slot, binding) in bindings.iter().enumerate() {
+                            let binding = match binding {
+                                Some(binding) => binding,
+                                None => continue,
+                            };
+                            // the slot keeps from and to, the editor shows them as a range and
+                            // whether it's the wrong way round
+                            let mut low = binding.from.min(binding.to);
+                            let mut high = binding.from.max(binding.to);
+                            let mut invert = binding.from > binding.to;
+                            let mut edited = false;
+                            ui.horizontal(|ui| {
+                                let (name, min, max) = PARAMETERS[binding.parameter];
+                                match overrides[slot] {
+                                    Some(value) => ui.label(format!(
+                                        "cc {} to {}, holding {:.2}",
+                                        binding.cc,
+                                        name,
+                                        min + (value * (max - min))
+                                    )),
+                                    None => ui.label(format!("cc {} to {}", binding.cc, name)),
+                                };
+                                edited |= ui
+                                    .add(egui::Slider::new(&mut low, 0.0..=1.0).text("min"))
+                                    .changed();
+                                edited |= ui
+                                    .add(egui::Slider::new(&mut high, 0.0..=1.0).text("max"))
+                                    .changed();
+                                edited |= ui.checkbox(&mut invert, "invert").changed();
+                                if ui.small_button("x").clicked() {
+                                    state.clear_binding(slot);
+                                }
+                            });
+                            if edited {
+                                let (from, to) = if invert { (high, low) } else { (low, high) };
+                                state.set_binding(slot, binding.cc, binding.parameter, from, to);
+                            }
+                        }
+                        if bindings.iter().all(Option::is_none) {
+                            ui.label("right-click a slider and move a controller to map it");
+                        }
+                        if let Some(error) = &state.learn_error {
+                            ui.colored_label(egui::Color32::RED, error.as_str());
+                        }
+                        if bindings.iter().any(Option::is_some) && ui.button("remove all").clicked()
+                        {
+                            state.clear_bindings();
+                        }
+                    });
                 });
 
+                // a second right-click on the same slider stops learning
+                if let Some(index) = learn.into_inner() {
+                    state.toggle_learning(index);
+                }
+
                 // TODO: Add a way for egui-baseview to send a closure that runs every frame without always
                 // repainting.
                 egui_ctx.request_repaint();
@@ -385,10 +2800,61 @@ struct State {
     degrade_intensity_value: String,
     degrade_speed_value: String,
     glitch_enum_value: String,
+    spectral_blur_value: String,
+    spectral_smoothing_value: String,
+    reverse_length_value: String,
+    shimmer_amount_value: String,
+    shimmer_semitones_value: String,
+    shimmer_tone_value: String,
+    gate_threshold_value: String,
+    gate_hold_value: String,
+    gate_attack_value: String,
+    gate_release_value: String,
+    duck_amount_value: String,
+    duck_threshold_value: String,
+    duck_attack_value: String,
+    duck_release_value: String,
+    lfo_rate_value: String,
+    lfo_phase_value: String,
+    lfo_spread_value: String,
+    midi_freeze_note_value: String,
+    midi_glitch_note_value: String,
+    mod_1_amount_value: String,
+    mod_2_amount_value: String,
+    mod_3_amount_value: String,
+    mod_4_amount_value: String,
+
+    presets: PresetManager,
+    preset: Option<usize>,   //index of the loaded preset
+    preset_values: Vec<f32>, //what it set every parameter to, for telling when it's been changed
+    preset_search: String,
+    preset_tag: Option<String>,
+    favourites_only: bool,
+    preset_name: String, //for save as
+    preset_error: Option<String>,
+    refresh_values: bool, //redo every value text next frame, after a preset changed them all
+    ab_slot: usize,
+    learning: Option<(usize, usize)>, //parameter waiting for a controller, and SHARED.last_cc when it started
+    learn_error: Option<String>,
+    instance: usize, //the id this editor gave its instance, see SHARED.instances
+    tank_resets_seen: usize, //how many of the instance's tank resets have been warned about
 }
 
 impl State {
-    pub fn new(model: ReverbModelUI<Reverb>) -> State {
+    pub fn new(mut model: ReverbModelUI<Reverb>) -> State {
+        // learning that was still going when the editor last closed has nobody left to finish it
+        model.midi_learn.set_from_normalized(0.0);
+        let instance = SHARED.new_instance_id();
+        model
+            .instance
+            .set_from_normalized(instance as f32 / shared::MAX_INSTANCE_ID as f32);
+
+        let ab_slot = if model.ab_slot.normalized() > 0.5 {
+            1
+        } else {
+            0
+        };
+
         State {
             model,
             g_value: String::new(),
@@ -396,8 +2862,504 @@ impl State {
             degrade_intensity_value: String::new(),
             degrade_speed_value: String::new(),
             glitch_enum_value: String::new(),
+            spectral_blur_value: String::new(),
+            spectral_smoothing_value: String::new(),
+            reverse_length_value: String::new(),
+            shimmer_amount_value: String::new(),
+            shimmer_semitones_value: String::new(),
+            shimmer_tone_value: String::new(),
+            gate_threshold_value: String::new(),
+            gate_hold_value: String::new(),
+            gate_attack_value: String::new(),
+            gate_release_value: String::new(),
+            duck_amount_value: String::new(),
+            duck_threshold_value: String::new(),
+            duck_attack_value: String::new(),
+            duck_release_value: String::new(),
+            lfo_rate_value: String::new(),
+            lfo_phase_value: String::new(),
+            lfo_spread_value: String::new(),
+            midi_freeze_note_value: String::new(),
+            midi_glitch_note_value: String::new(),
+            mod_1_amount_value: String::new(),
+            mod_2_amount_value: String::new(),
+            mod_3_amount_value: String::new(),
+            mod_4_amount_value: String::new(),
+
+            presets: PresetManager::load(),
+            preset: None,
+            preset_values: Vec::new(),
+            preset_search: String::new(),
+            preset_tag: None,
+            favourites_only: false,
+            preset_name: String::new(),
+            preset_error: None,
+            refresh_values: false,
+            ab_slot,
+            learning: None,
+            learn_error: None,
+            instance,
+            tank_resets_seen: 0,
+        }
+    }
+
+    // b starts out as a copy of a the first time it's picked. the active slot is the parameters
+    // themselves, the other one is stored in the model
+    fn select_slot(&mut self, slot: usize) {
+        if slot == self.ab_slot {
+            return;
+        }
+        let active = self.values();
+        let stored = self.stored_values().unwrap_or_else(|| active.clone());
+        self.set_values(&stored);
+        self.store_values(&active);
+        self.ab_slot = slot;
+        self.model.ab_slot.set_from_normalized(slot as f32);
+    }
+
+    // copies `from` over the other slot
+    fn copy_slot(&mut self, from: usize) {
+        if from == self.ab_slot {
+            let active = self.values();
+            self.store_values(&active);
+        } else if let Some(stored) = self.stored_values() {
+            self.set_values(&stored);
+        }
+    }
+
+    // the inactive slot, None until it's been filled
+    fn stored_values(&mut self) -> Option<Vec<f32>> {
+        if self.model.ab_stored.normalized() < 0.5 {
+            return None;
+        }
+        Some(self.values_of(&stored_parameters()))
+    }
+
+    fn store_values(&mut self, values: &[f32]) {
+        self.set_values_of(&stored_parameters(), values);
+        self.model.ab_stored.set_from_normalized(1.0);
+    }
+
+    // plain values of the sound parameters, which is what a and b hold
+    fn values(&mut self) -> Vec<f32> {
+        self.values_of(&sound_parameters())
+    }
+
+    fn set_values(&mut self, values: &[f32]) {
+        self.set_values_of(&sound_parameters(), values);
+    }
+
+    // plain values of the parameters at `indices`
+    fn values_of(&mut self, indices: &[usize]) -> Vec<f32> {
+        let params = ui_params!(self.model);
+        indices
+            .iter()
+            .map(|index| {
+                let (_, min, max) = PARAMETERS[*index];
+                min + (params[*index].normalized() * (max - min))
+            })
+            .collect()
+    }
+
+    fn set_values_of(&mut self, indices: &[usize], values: &[f32]) {
+        for (index, value) in indices.iter().zip(values.iter()) {
+            self.set_value(*index, *value);
+        }
+        self.refresh_values = true;
+    }
+
+    fn set_value(&mut self, index: usize, value: f32) {
+        let (_, min, max) = PARAMETERS[index];
+        ui_params!(self.model)[index].set_from_normalized((value - min) / (max - min));
+    }
+
+    // every parameter as the plain model, for reading the settings that take several parameters
+    fn plain_model(&mut self) -> ReverbModel {
+        let mut model = ReverbModel::default();
+        for (index, (param, (_, min, max))) in ui_params!(self.model)
+            .iter()
+            .zip(PARAMETERS.iter())
+            .enumerate()
+        {
+            if let Some(value) = model.value_mut(index) {
+                *value = min + (param.normalized() * (max - min));
+            }
+        }
+        model
+    }
+
+    fn set_binding(&mut self, slot: usize, cc: u8, parameter: usize, from: f32, to: f32) {
+        let start = MIDI_SLOTS_START + (4 * slot);
+        self.set_value(start, cc as f32);
+        self.set_value(start + 1, parameter as f32);
+        self.set_value(start + 2, from);
+        self.set_value(start + 3, to);
+    }
+
+    fn clear_binding(&mut self, slot: usize) {
+        self.set_value(MIDI_SLOTS_START + (4 * slot), midi::NO_CC);
+    }
+
+    // counts from when the instance was created, so resets from before the editor opened show too
+    fn tank_resets(&self) -> usize {
+        match SHARED.instance(self.instance) {
+            Some(instance) if instance.belongs_to(self.instance) => {
+                instance.tank_resets.load(Ordering::Relaxed)
+            }
+            _ => 0,
+        }
+    }
+
+    // normalized values the instance's cc mappings are holding their parameters at, by slot
+    fn overrides(&self) -> [Option<f32>; midi::SLOTS] {
+        let mut overrides = [None; midi::SLOTS];
+        if let Some(instance) = SHARED.instance(self.instance) {
+            if instance.belongs_to(self.instance) {
+                for (slot, value) in overrides.iter_mut().enumerate() {
+                    *value = instance.override_value(slot);
+                }
+            }
+        }
+        overrides
+    }
+
+    fn clear_bindings(&mut self) {
+        for slot in 0..midi::SLOTS {
+            self.clear_binding(slot);
+        }
+        self.learn_error = None;
+    }
+
+    fn toggle_learning(&mut self, parameter: usize) {
+        self.learning = match self.learning {
+            Some((learning, _)) if learning == parameter => None,
+            _ => Some((parameter, SHARED.last_cc.load(Ordering::Relaxed))),
+        };
+        self.learn_error = None;
+        self.set_learning_flag();
+    }
+
+    // tells this instance's audio thread whether to pass on the ccs it gets
+    fn set_learning_flag(&mut self) {
+        let learning = if self.learning.is_some() { 1.0 } else { 0.0 };
+        self.model.midi_learn.set_from_normalized(learning);
+    }
+
+    // binds the first cc to arrive since learning started. it replaces whatever either end was
+    // bound to before
+    fn follow_learning(&mut self) {
+        let (parameter, started) = match self.learning {
+            Some(learning) => learning,
+            None => return,
+        };
+        let last_cc = SHARED.last_cc.load(Ordering::Relaxed);
+        if last_cc == started {
+            return;
+        }
+        let cc = (last_cc & 0xff) as u8;
+        self.learning = None;
+        self.set_learning_flag();
+
+        let bindings = self.plain_model().cc_bindings();
+        for (slot, binding) in bindings.iter().enumerate() {
+            if let Some(binding) = binding {
+                if binding.cc == cc || binding.parameter == parameter {
+                    self.clear_binding(slot);
+                }
+            }
+        }
+        let bindings = self.plain_model().cc_bindings();
+        match bindings.iter().position(Option::is_none) {
+            Some(slot) => self.set_binding(slot, cc, parameter, 0.0, 1.0),
+            None => {
+                self.learn_error = Some(format!(
+                    "all {} mappings are in use, remove one first",
+                    midi::SLOTS
+                ))
+            }
+        }
+    }
+
+    fn load_preset(&mut self, index: usize) {
+        if let Some(entry) = self.presets.presets.get(index) {
+            let indices = sound_parameters();
+            let parameters: Vec<_> = indices.iter().map(|index| PARAMETERS[*index]).collect();
+            let defaults = default_values();
+            let defaults: Vec<f32> = indices.iter().map(|index| defaults[*index]).collect();
+            let values = entry.preset.values(&parameters, &defaults);
+            self.preset_name = entry.preset.name.clone();
+            self.set_values_of(&indices, &values);
+            self.preset_values = values;
+            self.preset = Some(index);
+        }
+    }
+
+    // anything moved further than the host could have rounded it since the preset was loaded
+    fn preset_modified(&mut self) -> bool {
+        let indices = sound_parameters();
+        let values = self.values_of(&indices);
+        self.preset.is_some()
+            && values
+                .iter()
+                .zip(self.preset_values.iter())
+                .zip(indices.iter())
+                .any(|((value, preset_value), index)| {
+                    let (_, min, max) = PARAMETERS[*index];
+                    (value - preset_value).abs() > 0.001 * (max - min)
+                })
+    }
+
+    // picks the user directory up again, keeping the loaded preset selected if it's still there
+    fn reload_presets(&mut self) {
+        let name = self
+            .preset
+            .and_then(|index| self.presets.presets.get(index))
+            .map(|entry| entry.preset.name.clone());
+        self.presets.reload();
+        self.preset = name.and_then(|name| self.presets.find(&name));
+        if self.preset.is_none() {
+            self.preset_values.clear();
+        }
+    }
+
+    fn user_preset_loaded(&self) -> bool {
+        match self
+            .preset
+            .and_then(|index| self.presets.presets.get(index))
+        {
+            Some(entry) => matches!(entry.source, PresetSource::User(_)),
+            None => false,
+        }
+    }
+
+    // indices of the presets the search, tag and favourites settings let through
+    fn visible_presets(&self) -> Vec<usize> {
+        self.presets
+            .presets
+            .iter()
+            .enumerate()
+            .filter(|(_, entry)| {
+                entry.preset.matches(&self.preset_search)
+                    && match &self.preset_tag {
+                        Some(tag) => entry.preset.has_tag(tag),
+                        None => true,
+                    }
+                    && (!self.favourites_only || self.presets.is_favourite(&entry.preset.name))
+            })
+            .map(|(index, _)| index)
+            .collect()
+    }
+
+    // moves through the visible presets, wrapping around at either end
+    fn step_preset(&mut self, forward: bool) {
+        let visible = self.visible_presets();
+        if visible.is_empty() {
+            return;
+        }
+        let next = match self
+            .preset
+            .and_then(|preset| visible.iter().position(|index| *index == preset))
+        {
+            Some(position) if forward => (position + 1) % visible.len(),
+            Some(position) => (position + visible.len() - 1) % visible.len(),
+            None if forward => 0,
+            None => visible.len() - 1,
+        };
+        self.load_preset(visible[next]);
+    }
+
+    // a user preset from the current values, keeping the tags of the loaded preset and its author
+    // if it's a user one
+    fn save_preset(&mut self) {
+        let name = self.preset_name.trim().to_string();
+        if name.is_empty() {
+            self.preset_error = Some("the preset needs a name".to_string());
+            return;
+        }
+        let (author, tags) = match self
+            .preset
+            .and_then(|index| self.presets.presets.get(index))
+        {
+            Some(entry) => (
+                match entry.source {
+                    PresetSource::User(_) => entry.preset.author.clone(),
+                    PresetSource::Factory => String::new(),
+                },
+                entry.preset.tags.clone(),
+            ),
+            None => (String::new(), Vec::new()),
+        };
+        let indices = sound_parameters();
+        let parameters: Vec<_> = indices.iter().map(|index| PARAMETERS[*index]).collect();
+        let values = self.values_of(&indices);
+        let preset = Preset::from_values(&name, &author, tags, &parameters, &values);
+        match self.presets.save(&preset) {
+            Ok(index) => {
+                self.preset = Some(index);
+                self.preset_values = values;
+                self.preset_error = None;
+            }
+            Err(error) => self.preset_error = Some(format!("couldn't save the preset: {}", error)),
+        }
+    }
+
+    fn delete_preset(&mut self) {
+        if !self.user_preset_loaded() {
+            return;
+        }
+        if let Some(index) = self.preset {
+            match self.presets.delete(index) {
+                Ok(()) => {
+                    self.preset = None;
+                    self.preset_values.clear();
+                    self.preset_error = None;
+                }
+                Err(error) => {
+                    self.preset_error = Some(format!("couldn't delete the preset: {}", error))
+                }
+            }
         }
     }
 }
 
 baseplug::vst2!(Reverb, b"rvrb");
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // indices into PARAMETERS end up in sessions (mod destinations, midi mappings), so it has to
+    // match the model field for field
+    #[test]
+    fn parameter_table_matches_the_model() {
+        let text = serde_json::to_string(&ReverbModel::default()).unwrap();
+        let fields: serde_json::Map<String, serde_json::Value> =
+            serde_json::from_str(&text).unwrap();
+        assert_eq!(fields.len(), PARAMETERS.len());
+
+        // serde writes the fields in the order they're declared
+        let mut position = 0;
+        for (name, min, max) in PARAMETERS.iter() {
+            let key = format!("\"{}\":", name);
+            match text[position..].find(&key) {
+                Some(found) => position += found + key.len(),
+                None => panic!("{} isn't where the table has it", name),
+            }
+            let default = fields[*name].as_f64().unwrap() as f32;
+            assert!(min < max, "{} has an empty range", name);
+            assert!(
+                default >= *min && default <= *max,
+                "{} defaults outside its range",
+                name
+            );
+        }
+
+        for (index, (name, _, _)) in PARAMETERS.iter().enumerate() {
+            let mut model = ReverbModel::default();
+            *model
+                .value_mut(index)
+                .unwrap_or_else(|| panic!("no value_mut for {}", name)) = 1234.5;
+            let fields = serde_json::to_value(&model).unwrap();
+            assert_eq!(fields[*name], 1234.5, "value_mut({}) isn't {}", index, name);
+        }
+        assert!(ReverbModel::default().value_mut(PARAMETERS.len()).is_none());
+
+        for name in SOUND_PARAMETER_NAMES
+            .iter()
+            .chain(SMOOTHED_PARAMETERS.iter().map(|(name, _, _)| name))
+        {
+            assert!(
+                parameter_index(name).is_some(),
+                "{} isn't a parameter",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn mod_destinations_are_stable() {
+        let spectral_smoothing = parameter_index("spectral_smoothing").unwrap();
+        assert!(is_mod_destination(spectral_smoothing));
+        assert!(
+            mod_slot(1.0, spectral_smoothing as f32, 1.0).source != modulation::ModSource::None
+        );
+        assert!(!is_mod_destination(
+            parameter_index("mod_1_amount").unwrap()
+        ));
+        assert!(!is_mod_destination(parameter_index("quality").unwrap()));
+        assert!(mod_slot(1.0, MOD_DESTINATION_MAX, 1.0).source == modulation::ModSource::None);
+        assert!(PARAMETERS.len() as f32 <= MOD_DESTINATION_MAX);
+    }
+
+    // the inactive a/b slot is stored a field per sound parameter, and has to start out the same
+    #[test]
+    fn ab_snapshot_matches_the_sound_parameters() {
+        assert_eq!(PARAMETERS[AB_STORED].0, "ab_stored");
+        let defaults = default_values();
+        for (sound, stored) in sound_parameters().into_iter().zip(stored_parameters()) {
+            let (name, min, max) = PARAMETERS[sound];
+            assert_eq!(
+                PARAMETERS[stored],
+                (&*format!("ab_stored_{}", name), min, max)
+            );
+            assert_eq!(defaults[stored], defaults[sound], "{}", name);
+        }
+        assert_eq!(sound_parameters().len(), SOUND_PARAMETER_NAMES.len());
+    }
+
+    // a fresh instance doesn't react to any controller until something's mapped, and every
+    // parameter a mapping can point at has to fit its range
+    #[test]
+    fn midi_mappings_start_empty() {
+        assert!(ReverbModel::default()
+            .cc_bindings()
+            .iter()
+            .all(Option::is_none));
+        let (_, _, max) = PARAMETERS[MIDI_SLOTS_START + 1];
+        assert!(sound_parameters().iter().all(|index| *index as f32 <= max));
+        assert!(cc_binding(1.0, max, 0.0, 1.0).is_none());
+    }
+
+    // the editor hands its id over as a normalized value, so every id has to come back out whole
+    #[test]
+    fn instance_ids_survive_the_model() {
+        let (_, min, max) = PARAMETERS[parameter_index("instance").unwrap()];
+        assert_eq!(max as usize, shared::MAX_INSTANCE_ID);
+        for id in 1..=shared::MAX_INSTANCE_ID {
+            let normalized = id as f32 / shared::MAX_INSTANCE_ID as f32;
+            assert_eq!((min + (normalized * (max - min))).round() as usize, id);
+        }
+        for _ in 0..10 {
+            let id = SHARED.new_instance_id();
+            assert!((1..=shared::MAX_INSTANCE_ID).contains(&id));
+            assert!(SHARED.instance(id).is_some());
+        }
+        assert!(SHARED.instance(0).is_none());
+    }
+
+    // sessions from before the newer glitch modes only ever have glitch_enum set
+    #[test]
+    fn legacy_glitch_values_keep_their_modes() {
+        for (value, mode) in [
+            (0.0, GlitchEnum::Not),
+            (0.3, GlitchEnum::Not),
+            (0.31, GlitchEnum::Indeed),
+            (0.51, GlitchEnum::Indeed),
+            (0.7, GlitchEnum::Indeed),
+            (0.71, GlitchEnum::Lfo),
+            (0.86, GlitchEnum::Lfo),
+            (1.0, GlitchEnum::Lfo),
+        ]
+        .iter()
+        {
+            assert!(GlitchEnum::from_values(*value, 0.0) == *mode, "{}", value);
+        }
+        for mode in GLITCH_NOTE_MODES.iter() {
+            let (glitch, reverse) = match mode.value() {
+                Some(value) => (value, 0.0),
+                None => (0.0, 1.0),
+            };
+            assert!(GlitchEnum::from_values(glitch, reverse) == *mode);
+        }
+    }
+}
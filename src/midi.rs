@@ -0,0 +1,138 @@
+// what the plugin remembers from incoming midi between process calls
+
+// how many cc mappings an instance has room for, each is a set of model fields so it's saved
+// with the plugin's state
+pub const SLOTS: usize = 8;
+// a mapping slot's cc parameter at or above this is empty
+pub const NO_CC: f32 = 128.0;
+
+#[derive(Clone, Copy)]
+pub struct CcBinding {
+    pub cc: u8,
+    pub parameter: usize, //index into the parameter table
+    pub from: f32,        //normalized values the bottom and top of the controller give,
+    pub to: f32,          //inverted when to is below from
+}
+
+impl CcBinding {
+    // takes the controller value (0.0 to 1.0) and returns the normalized parameter value
+    pub fn map(&self, value: f32) -> f32 {
+        self.from + (value * (self.to - self.from))
+    }
+}
+
+// a bound cc holds its parameter until the host or the editor moves that parameter again
+#[derive(Clone, Copy)]
+pub struct Override {
+    pub value: f32,    //0.0 to 1.0
+    host: Option<f32>, //the parameter's own normalized value once the cc took over
+}
+
+// how far the host has to move a parameter before it takes it back from a cc, normalized
+const HOST_TOLERANCE: f32 = 0.001;
+
+pub struct MidiState {
+    pub freeze: Option<f32>, //strength the freeze note turned freeze on with, from its velocity
+    pub glitch: Option<usize>, //which of the glitch notes is held
+    glitch_note: u8,
+    pub mod_wheel: f32,
+    pub velocity: f32,
+    pub overrides: Vec<Option<Override>>, //per parameter, set by bound ccs
+}
+
+impl MidiState {
+    pub fn new(parameters: usize) -> Self {
+        Self {
+            freeze: None,
+            glitch: None,
+            glitch_note: 0,
+            mod_wheel: 0.0,
+            velocity: 0.0,
+            overrides: vec![None; parameters],
+        }
+    }
+
+    // glitch notes are glitch_base and the `glitch_count - 1` notes above it.
+    // returns the controller number and value (0.0 to 1.0) of control changes
+    pub fn handle(
+        &mut self,
+        data: [u8; 3],
+        freeze_note: u8,
+        glitch_base: u8,
+        glitch_count: usize,
+    ) -> Option<(u8, f32)> {
+        let is_glitch_note =
+            |note: u8| note >= glitch_base && note < glitch_base + glitch_count as u8;
+
+        match data[0] & 0xf0 {
+            // note on, velocity zero is a note off. the freeze note toggles freeze on and off
+            0x90 if data[2] > 0 => {
+                let velocity = data[2] as f32 / 127.0;
+                self.velocity = velocity;
+                if data[1] == freeze_note {
+                    self.freeze = match self.freeze {
+                        Some(_) => None,
+                        None => Some(velocity),
+                    };
+                } else if is_glitch_note(data[1]) {
+                    self.glitch = Some((data[1] - glitch_base) as usize);
+                    self.glitch_note = data[1];
+                }
+                None
+            }
+            0x80 | 0x90 => {
+                if self.glitch.is_some() && data[1] == self.glitch_note {
+                    self.glitch = None;
+                }
+                None
+            }
+            // control change
+            0xb0 => {
+                let value = data[2] as f32 / 127.0;
+                if data[1] == 1 {
+                    self.mod_wheel = value;
+                }
+                Some((data[1], value))
+            }
+            _ => None,
+        }
+    }
+
+    pub fn apply_binding(&mut self, binding: &CcBinding, value: f32) {
+        if let Some(slot) = self.overrides.get_mut(binding.parameter) {
+            let host = slot.and_then(|current| current.host);
+            *slot = Some(Override {
+                value: binding.map(value),
+                host,
+            });
+        }
+    }
+
+    // lets go of parameters whose binding was removed
+    pub fn release_unbound(&mut self, bindings: &[Option<CcBinding>]) {
+        for (parameter, slot) in self.overrides.iter_mut().enumerate() {
+            if slot.is_some()
+                && !bindings
+                    .iter()
+                    .flatten()
+                    .any(|binding| binding.parameter == parameter)
+            {
+                *slot = None;
+            }
+        }
+    }
+
+    // called at the end of every block with the parameter's own normalized value. the first one
+    // after a cc is what the host had, and the cc gives way as soon as that changes
+    pub fn follow_host(&mut self, parameter: usize, host: f32) {
+        if let Some(slot) = self.overrides.get_mut(parameter) {
+            if let Some(current) = slot {
+                match current.host {
+                    None => current.host = Some(host),
+                    Some(before) if (host - before).abs() > HOST_TOLERANCE => *slot = None,
+                    Some(_) => (),
+                }
+            }
+        }
+    }
+}
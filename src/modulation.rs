@@ -0,0 +1,87 @@
+// modulation sources, and the matrix that routes them onto parameters
+
+pub const SLOTS: usize = 4;
+
+#[derive(PartialEq, Clone, Copy)]
+pub enum ModSource {
+    None,
+    Lfo,
+    LfoRight, //the lfo with stereo spread applied
+    Envelope,
+    Random,
+    ModWheel,
+    Velocity,
+}
+
+// the source parameters are spread evenly over these
+pub const SOURCES: [ModSource; 7] = [
+    ModSource::None,
+    ModSource::Lfo,
+    ModSource::LfoRight,
+    ModSource::Envelope,
+    ModSource::Random,
+    ModSource::ModWheel,
+    ModSource::Velocity,
+];
+pub const SOURCE_NAMES: [&str; 7] = [
+    "none",
+    "lfo",
+    "lfo (right)",
+    "envelope",
+    "random",
+    "mod wheel (cc 1)",
+    "velocity",
+];
+
+// latest value of every source, the lfos and random are -1.0 to 1.0, the rest 0.0 to 1.0.
+// mod wheel and velocity come from midi
+#[derive(Default)]
+pub struct ModSources {
+    pub lfo: f32,
+    pub lfo_right: f32,
+    pub envelope: f32,
+    pub random: f32,
+    pub mod_wheel: f32,
+    pub velocity: f32,
+}
+
+impl ModSources {
+    pub fn value(&self, source: ModSource) -> f32 {
+        match source {
+            ModSource::None => 0.0,
+            ModSource::Lfo => self.lfo,
+            ModSource::LfoRight => self.lfo_right,
+            ModSource::Envelope => self.envelope,
+            ModSource::Random => self.random,
+            ModSource::ModWheel => self.mod_wheel,
+            ModSource::Velocity => self.velocity,
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct ModSlot {
+    pub source: ModSource,
+    pub destination: usize, //index into the parameter table
+    pub amount: f32,        //-1.0 to 1.0, a full sweep of the destination's range
+}
+
+// adds every slot's contribution onto the offset of its destination, in the destination's units.
+// parameters are (name, min, max)
+pub fn evaluate(
+    slots: &[ModSlot],
+    sources: &ModSources,
+    parameters: &[(&str, f32, f32)],
+    offsets: &mut [f32],
+) {
+    for offset in offsets.iter_mut() {
+        *offset = 0.0;
+    }
+    for slot in slots {
+        if slot.source == ModSource::None || slot.destination >= offsets.len() {
+            continue;
+        }
+        let (_, min, max) = parameters[slot.destination];
+        offsets[slot.destination] += sources.value(slot.source) * slot.amount * (max - min);
+    }
+}
@@ -0,0 +1,296 @@
+// named sets of parameter values. the factory bank is compiled in, user presets are json files in
+// the user directory that can be edited by hand
+
+use std::collections::BTreeMap;
+use std::ffi::OsStr;
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use crate::storage;
+
+// bumped whenever a parameter changes meaning, so older files can be converted when they're read
+pub const PRESET_VERSION: u32 = 1;
+
+const FACTORY_PRESETS: [&str; 9] = [
+    include_str!("../presets/small_room.json"),
+    include_str!("../presets/damped_room.json"),
+    include_str!("../presets/big_hall.json"),
+    include_str!("../presets/shimmer_hall.json"),
+    include_str!("../presets/crunchy_tape.json"),
+    include_str!("../presets/bitcrushed_cave.json"),
+    include_str!("../presets/wobble.json"),
+    include_str!("../presets/reverse_swell.json"),
+    include_str!("../presets/gated_stutter.json"),
+];
+
+// values are in each parameter's own units, like the host sees them. parameters that are left out
+// keep their defaults
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Preset {
+    pub version: u32,
+    pub name: String,
+    #[serde(default)]
+    pub author: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    pub parameters: BTreeMap<String, f32>,
+}
+
+impl Preset {
+    /// Every parameter in `parameters`, in order, from the plain `values`.
+    pub fn from_values(
+        name: &str,
+        author: &str,
+        tags: Vec<String>,
+        parameters: &[(&str, f32, f32)],
+        values: &[f32],
+    ) -> Self {
+        Self {
+            version: PRESET_VERSION,
+            name: name.to_string(),
+            author: author.to_string(),
+            tags,
+            parameters: parameters
+                .iter()
+                .zip(values.iter())
+                .map(|((name, _, _), value)| (name.to_string(), *value))
+                .collect(),
+        }
+    }
+
+    /// A value for every parameter in `parameters`, in order, clamped to its range. The default is
+    /// used where the preset doesn't have one. Names the plugin doesn't know are ignored.
+    pub fn values(&self, parameters: &[(&str, f32, f32)], defaults: &[f32]) -> Vec<f32> {
+        parameters
+            .iter()
+            .zip(defaults.iter())
+            .map(
+                |((name, min, max), default)| match self.parameters.get(*name) {
+                    Some(value) => value.clamp(*min, *max),
+                    None => *default,
+                },
+            )
+            .collect()
+    }
+
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let preset: Self = serde_json::from_str(text).map_err(|e| e.to_string())?;
+        if preset.version > PRESET_VERSION {
+            return Err(format!(
+                "made by a newer version of the plugin (format {})",
+                preset.version
+            ));
+        }
+        Ok(preset)
+    }
+
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|own| own.eq_ignore_ascii_case(tag))
+    }
+
+    // the search box looks through names, authors and tags, ignoring case
+    pub fn matches(&self, search: &str) -> bool {
+        let search = search.to_lowercase();
+        self.name.to_lowercase().contains(&search)
+            || self.author.to_lowercase().contains(&search)
+            || self
+                .tags
+                .iter()
+                .any(|tag| tag.to_lowercase().contains(&search))
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum PresetSource {
+    Factory,
+    User(PathBuf),
+}
+
+pub struct PresetEntry {
+    pub preset: Preset,
+    pub source: PresetSource,
+}
+
+// the factory bank followed by the user's presets, each sorted by name
+pub struct PresetManager {
+    pub presets: Vec<PresetEntry>,
+    pub errors: Vec<String>, //user files that couldn't be read, for the editor to show
+    pub favourites: Vec<String>, //preset names, kept apart from the presets so factory ones can be favourites too
+}
+
+impl PresetManager {
+    pub fn load() -> Self {
+        let mut manager = Self {
+            presets: Vec::new(),
+            errors: Vec::new(),
+            favourites: storage::load_favourites(),
+        };
+        manager.reload();
+        manager
+    }
+
+    // reads the user directory again, for when files were added or edited outside the plugin
+    pub fn reload(&mut self) {
+        self.presets.clear();
+        self.errors.clear();
+
+        let mut factory: Vec<PresetEntry> = FACTORY_PRESETS
+            .iter()
+            .map(|text| PresetEntry {
+                preset: Preset::parse(text).expect("the factory presets should parse"),
+                source: PresetSource::Factory,
+            })
+            .collect();
+        factory.sort_by(|a, b| a.preset.name.cmp(&b.preset.name));
+        self.presets.extend(factory);
+
+        let mut user = Vec::new();
+        for (path, text) in storage::read_presets() {
+            match Preset::parse(&text) {
+                Ok(preset) => user.push(PresetEntry {
+                    preset,
+                    source: PresetSource::User(path),
+                }),
+                Err(error) => self.errors.push(format!("{}: {}", path.display(), error)),
+            }
+        }
+        user.sort_by(|a, b| a.preset.name.cmp(&b.preset.name));
+        self.presets.extend(user);
+    }
+
+    pub fn find(&self, name: &str) -> Option<usize> {
+        self.presets
+            .iter()
+            .position(|entry| entry.preset.name == name)
+    }
+
+    /// Writes the preset to the user directory, replacing a user preset with the same name.
+    /// Returns its index once the list has been reloaded. A different user preset that would end up
+    /// in the same file is an error rather than being overwritten.
+    pub fn save(&mut self, preset: &Preset) -> std::io::Result<usize> {
+        let file_name = file_name(&preset.name);
+        let clash = self.presets.iter().find(|entry| match &entry.source {
+            PresetSource::User(path) => {
+                entry.preset.name != preset.name && path.file_name() == Some(OsStr::new(&file_name))
+            }
+            PresetSource::Factory => false,
+        });
+        if let Some(entry) = clash {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::AlreadyExists,
+                format!(
+                    "\"{}\" is saved under the same file name",
+                    entry.preset.name
+                ),
+            ));
+        }
+
+        let text = serde_json::to_string_pretty(preset)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+        storage::write_preset(&file_name, &text)?;
+        self.reload();
+        Ok(self.find_user(&preset.name).unwrap_or(0))
+    }
+
+    /// Only user presets can be deleted, factory ones are left alone.
+    pub fn delete(&mut self, index: usize) -> std::io::Result<()> {
+        if let Some(PresetSource::User(path)) = self.presets.get(index).map(|entry| &entry.source) {
+            storage::delete_preset(path)?;
+            self.reload();
+        }
+        Ok(())
+    }
+
+    pub fn is_favourite(&self, name: &str) -> bool {
+        self.favourites.iter().any(|favourite| favourite == name)
+    }
+
+    pub fn toggle_favourite(&mut self, name: &str) -> std::io::Result<()> {
+        if self.is_favourite(name) {
+            self.favourites.retain(|favourite| favourite != name);
+        } else {
+            self.favourites.push(name.to_string());
+        }
+        storage::save_favourites(&self.favourites)
+    }
+
+    // every tag any preset has, sorted
+    pub fn tags(&self) -> Vec<String> {
+        let mut tags: Vec<String> = self
+            .presets
+            .iter()
+            .flat_map(|entry| entry.preset.tags.iter().map(|tag| tag.to_lowercase()))
+            .collect();
+        tags.sort();
+        tags.dedup();
+        tags
+    }
+
+    fn find_user(&self, name: &str) -> Option<usize> {
+        self.presets.iter().position(|entry| {
+            entry.preset.name == name && matches!(entry.source, PresetSource::User(_))
+        })
+    }
+}
+
+// a file name that's safe on every platform. names that only differ in punctuation end up sharing
+// a file, which `save` refuses
+fn file_name(name: &str) -> String {
+    let stem: String = name
+        .trim()
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '-' || c == '_' || c == ' ' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if stem.is_empty() {
+        "untitled.json".to_string()
+    } else {
+        format!("{}.json", stem)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{PARAMETERS, SOUND_PARAMETER_NAMES};
+
+    // reload expects every one of them to parse, and they should only set sound parameters
+    #[test]
+    fn factory_presets_load() {
+        let mut names = Vec::new();
+        for text in FACTORY_PRESETS.iter() {
+            let preset = Preset::parse(text)
+                .unwrap_or_else(|error| panic!("a factory preset doesn't parse: {}", error));
+            for (parameter, value) in preset.parameters.iter() {
+                let (_, min, max) = PARAMETERS
+                    .iter()
+                    .find(|(name, _, _)| name == parameter)
+                    .unwrap_or_else(|| panic!("{} sets an unknown {}", preset.name, parameter));
+                assert!(
+                    SOUND_PARAMETER_NAMES.contains(&parameter.as_str()),
+                    "{} sets {}, which isn't a sound parameter",
+                    preset.name,
+                    parameter
+                );
+                assert!(
+                    value >= min && value <= max,
+                    "{} sets {} out of range",
+                    preset.name,
+                    parameter
+                );
+            }
+            assert!(
+                !names.contains(&preset.name),
+                "{} is there twice",
+                preset.name
+            );
+            names.push(preset.name);
+        }
+    }
+}
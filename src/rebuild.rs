@@ -0,0 +1,239 @@
+// builds replacements for the plugin's dsp on a background thread, so the audio thread never has to
+// allocate the new delay lines or free the old ones
+
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use ringbuf::{Consumer, Producer, RingBuffer};
+
+use crate::storage;
+use crate::topology::{self, Tanks, Topology};
+
+// how often the topology file is checked for changes
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+// the audio thread's end of the queue old dsp goes back to a worker on. whatever doesn't fit is held
+// until it does, so nothing is ever dropped on the audio thread
+struct Retiring<T> {
+    queue: Producer<T>,
+    pending: Option<T>,
+}
+
+impl<T> Retiring<T> {
+    fn new(queue: Producer<T>) -> Self {
+        Self {
+            queue,
+            pending: None,
+        }
+    }
+
+    // false while something's still waiting for room
+    fn flush(&mut self) -> bool {
+        if let Some(old) = self.pending.take() {
+            if let Err(old) = self.queue.push(old) {
+                self.pending = Some(old);
+                return false;
+            }
+        }
+        true
+    }
+
+    // the owners only hand out something new once the last thing held here has gone, and
+    // everything retired was handed out first, so there's never more than one waiting
+    fn retire(&mut self, old: T) {
+        self.flush();
+        if let Err(old) = self.queue.push(old) {
+            self.pending = Some(old);
+        }
+    }
+}
+
+pub struct Rebuilder<T: Send + 'static> {
+    requests: Producer<f32>, //sample rates to build for
+    built: Consumer<T>,
+    retired: Retiring<T>, //old ones to be dropped on the worker
+    stop: Arc<AtomicBool>,
+    worker: Option<JoinHandle<()>>,
+}
+
+impl<T: Send + 'static> Rebuilder<T> {
+    pub fn new(build: impl Fn(f32) -> T + Send + 'static) -> Self {
+        let (requests, mut request_consumer) = RingBuffer::<f32>::new(4).split();
+        let (mut built_producer, built) = RingBuffer::<T>::new(4).split();
+        let (retired, mut retired_consumer) = RingBuffer::<T>::new(4).split();
+        let stop = Arc::new(AtomicBool::new(false));
+
+        let worker_stop = stop.clone();
+        let worker = thread::spawn(move || {
+            while !worker_stop.load(Ordering::Relaxed) {
+                while let Some(sample_rate) = request_consumer.pop() {
+                    // if nobody's picked up the last few, this one can go too
+                    let _ = built_producer.push(build(sample_rate));
+                }
+                while retired_consumer.pop().is_some() {}
+                // sleeps until there's a request, something retired or it's time to stop
+                thread::park();
+            }
+        });
+
+        Self {
+            requests,
+            built,
+            retired: Retiring::new(retired),
+            stop,
+            worker: Some(worker),
+        }
+    }
+
+    fn wake(&self) {
+        if let Some(worker) = &self.worker {
+            worker.thread().unpark();
+        }
+    }
+
+    /// False if the request couldn't be queued, try again later.
+    pub fn request(&mut self, sample_rate: f32) -> bool {
+        let queued = self.requests.push(sample_rate).is_ok();
+        self.wake();
+        queued
+    }
+
+    // nothing new comes out until whatever was last retired has made it onto the queue
+    pub fn take(&mut self) -> Option<T> {
+        if !self.retired.flush() {
+            self.wake();
+            return None;
+        }
+        self.built.pop()
+    }
+
+    // hands something from `take`, or what it replaced, back to be dropped on the worker
+    pub fn retire(&mut self, old: T) {
+        self.retired.retire(old);
+        self.wake();
+    }
+}
+
+impl<T: Send + 'static> Drop for Rebuilder<T> {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(worker) = self.worker.take() {
+            worker.thread().unpark();
+            let _ = worker.join();
+        }
+    }
+}
+
+// rebuilds the tanks whenever the topology file changes on disk, so it can be edited while the
+// plugin is running. a file that doesn't parse is reported and the old tanks are kept. only started
+// when there was a topology file to begin with, so a new file needs the plugin loaded again
+pub struct TopologyWatcher {
+    built: Consumer<Tanks>,
+    retired: Retiring<Tanks>,
+    sample_rate: Arc<AtomicU32>, //bits of the f32 rate to build for
+    stop: Arc<AtomicBool>,
+    worker: Option<JoinHandle<()>>,
+}
+
+impl TopologyWatcher {
+    pub fn new(sample_rate: f32) -> Self {
+        let (mut built_producer, built) = RingBuffer::<Tanks>::new(4).split();
+        let (retired, mut retired_consumer) = RingBuffer::<Tanks>::new(4).split();
+        let sample_rate = Arc::new(AtomicU32::new(sample_rate.to_bits()));
+        let stop = Arc::new(AtomicBool::new(false));
+
+        // whatever's on disk now is what the plugin was just built from
+        let mut modified = storage::topology_modified();
+        let worker_rate = sample_rate.clone();
+        let worker_stop = stop.clone();
+        let worker = thread::spawn(move || {
+            while !worker_stop.load(Ordering::Relaxed) {
+                let now = storage::topology_modified();
+                // that includes the file going away, which goes back to the default
+                if now != modified {
+                    modified = now;
+                    match Topology::load() {
+                        Ok(topology) => {
+                            topology::report_error(None);
+                            let rate = f32::from_bits(worker_rate.load(Ordering::Relaxed));
+                            let _ = built_producer.push(topology.build(rate));
+                        }
+                        Err(error) => topology::report_error(Some(error)),
+                    }
+                }
+                while retired_consumer.pop().is_some() {}
+                thread::park_timeout(POLL_INTERVAL);
+            }
+        });
+
+        Self {
+            built,
+            retired: Retiring::new(retired),
+            sample_rate,
+            stop,
+            worker: Some(worker),
+        }
+    }
+
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        self.sample_rate
+            .store(sample_rate.to_bits(), Ordering::Relaxed);
+    }
+
+    // tanks built since the last call, once the last retired ones have made it onto the queue.
+    // they might be for an older sample rate
+    pub fn take(&mut self) -> Option<Tanks> {
+        if !self.retired.flush() {
+            return None;
+        }
+        self.built.pop()
+    }
+
+    pub fn retire(&mut self, old: Tanks) {
+        self.retired.retire(old);
+    }
+}
+
+impl Drop for TopologyWatcher {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(worker) = self.worker.take() {
+            worker.thread().unpark();
+            let _ = worker.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicUsize;
+
+    struct Counted(Arc<AtomicUsize>);
+
+    impl Drop for Counted {
+        fn drop(&mut self) {
+            self.0.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    // a full queue holds on to what's retired rather than dropping it on the caller's thread
+    #[test]
+    fn retiring_to_a_full_queue_drops_nothing() {
+        let dropped = Arc::new(AtomicUsize::new(0));
+        let (queue, mut worker) = RingBuffer::new(1).split();
+        let mut retiring = Retiring::new(queue);
+        retiring.retire(Counted(dropped.clone()));
+        retiring.retire(Counted(dropped.clone()));
+        assert_eq!(dropped.load(Ordering::Relaxed), 0);
+        assert!(!retiring.flush());
+
+        drop(worker.pop());
+        assert_eq!(dropped.load(Ordering::Relaxed), 1);
+        assert!(retiring.flush());
+        drop(worker.pop());
+        assert_eq!(dropped.load(Ordering::Relaxed), 2);
+    }
+}
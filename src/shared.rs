@@ -0,0 +1,99 @@
+// state shared between the audio thread and the editor. baseplug's ui_open doesn't hand the editor
+// the plugin instance, so this is process-wide and every instance in a process shares it. what
+// belongs to one instance goes in `instances`, under an id its editor puts in the model
+
+use crate::midi;
+use std::sync::atomic::{AtomicU32, AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// how many instances can report to their editors at once. two whose ids land on the same slot
+// take turns, and each editor ignores what the other wrote
+pub const INSTANCES: usize = 64;
+// ids go up to this, it has to come through the model as a normalized value intact
+pub const MAX_INSTANCE_ID: usize = 65535;
+
+pub struct Instance {
+    id: AtomicUsize, //the id of the instance that last wrote here, 0 for none
+    pub tank_resets: AtomicUsize, //how many times the output went non-finite and got cleared
+    overrides: [AtomicU32; midi::SLOTS], //what each cc mapping holds its parameter at, as f32 bits
+}
+
+// bits of an override slot whose mapping isn't holding its parameter, a nan no cc can produce
+const NO_OVERRIDE: u32 = u32::MAX;
+
+impl Instance {
+    // the audio thread takes the slot over for its own id before writing to it
+    pub fn claim(&self, id: usize) {
+        self.id.store(id, Ordering::Relaxed);
+    }
+
+    pub fn belongs_to(&self, id: usize) -> bool {
+        self.id.load(Ordering::Relaxed) == id
+    }
+
+    // normalized, for the mapping in `slot`
+    pub fn set_override(&self, slot: usize, value: Option<f32>) {
+        let bits = value.map_or(NO_OVERRIDE, f32::to_bits);
+        self.overrides[slot].store(bits, Ordering::Relaxed);
+    }
+
+    pub fn override_value(&self, slot: usize) -> Option<f32> {
+        match self.overrides[slot].load(Ordering::Relaxed) {
+            NO_OVERRIDE => None,
+            bits => Some(f32::from_bits(bits)),
+        }
+    }
+}
+
+pub struct Shared {
+    // the last cc received by an instance that's midi learning in the low byte, above it a count of
+    // ccs so the editor can tell a controller moved again
+    pub last_cc: AtomicUsize,
+    pub topology_error: Mutex<Option<String>>, //why the topology file didn't load, never touched by the audio thread
+    pub instances: [Instance; INSTANCES],
+    next_instance_id: AtomicUsize,
+}
+
+impl Shared {
+    // a new id for an editor to give its instance. it starts off the clock so an id saved with a
+    // session is unlikely to match one handed out now
+    pub fn new_instance_id(&self) -> usize {
+        let seed = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |time| time.subsec_nanos() as usize);
+        let _ = self.next_instance_id.compare_exchange(
+            0,
+            (seed % MAX_INSTANCE_ID) + 1,
+            Ordering::Relaxed,
+            Ordering::Relaxed,
+        );
+        let id = self.next_instance_id.fetch_add(1, Ordering::Relaxed);
+        ((id - 1) % MAX_INSTANCE_ID) + 1
+    }
+
+    // the slot the instance with this id reports to, None for an instance no editor has named
+    pub fn instance(&self, id: usize) -> Option<&Instance> {
+        if id == 0 {
+            return None;
+        }
+        self.instances.get(id % INSTANCES)
+    }
+}
+
+// only ever used to fill the arrays below
+#[allow(clippy::declare_interior_mutable_const)]
+const NOT_OVERRIDDEN: AtomicU32 = AtomicU32::new(NO_OVERRIDE);
+#[allow(clippy::declare_interior_mutable_const)]
+const NO_INSTANCE: Instance = Instance {
+    id: AtomicUsize::new(0),
+    tank_resets: AtomicUsize::new(0),
+    overrides: [NOT_OVERRIDDEN; midi::SLOTS],
+};
+
+pub static SHARED: Shared = Shared {
+    last_cc: AtomicUsize::new(0),
+    topology_error: Mutex::new(None),
+    instances: [NO_INSTANCE; INSTANCES],
+    next_instance_id: AtomicUsize::new(0),
+};
@@ -0,0 +1,77 @@
+// files kept in the per-user config directory
+
+use std::ffi::OsStr;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+pub fn user_dir() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("schroeder_one"))
+}
+
+fn topology_path() -> Option<PathBuf> {
+    user_dir().map(|dir| dir.join("topology.json"))
+}
+
+// None if there's no topology file
+pub fn read_topology() -> Option<String> {
+    fs::read_to_string(topology_path()?).ok()
+}
+
+// None if there's no topology file, so it can be told apart from one that's been edited
+pub fn topology_modified() -> Option<SystemTime> {
+    fs::metadata(topology_path()?).ok()?.modified().ok()
+}
+
+fn presets_dir() -> Option<PathBuf> {
+    user_dir().map(|dir| dir.join("presets"))
+}
+
+// every .json file in the presets directory with its contents, skipping ones that can't be read
+pub fn read_presets() -> Vec<(PathBuf, String)> {
+    let entries = match presets_dir().and_then(|dir| fs::read_dir(dir).ok()) {
+        Some(entries) => entries,
+        None => return Vec::new(),
+    };
+    entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension() == Some(OsStr::new("json")))
+        .filter_map(|path| fs::read_to_string(&path).ok().map(|text| (path, text)))
+        .collect()
+}
+
+pub fn write_preset(file_name: &str, text: &str) -> std::io::Result<PathBuf> {
+    let dir = presets_dir()
+        .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::NotFound, "no config directory"))?;
+    fs::create_dir_all(&dir)?;
+    let path = dir.join(file_name);
+    fs::write(&path, text)?;
+    Ok(path)
+}
+
+pub fn delete_preset(path: &Path) -> std::io::Result<()> {
+    fs::remove_file(path)
+}
+
+fn favourites_path() -> Option<PathBuf> {
+    user_dir().map(|dir| dir.join("favourites.json"))
+}
+
+// names of the favourite presets, empty if none have been saved yet
+pub fn load_favourites() -> Vec<String> {
+    favourites_path()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|text| serde_json::from_str(&text).ok())
+        .unwrap_or_default()
+}
+
+pub fn save_favourites(favourites: &[String]) -> std::io::Result<()> {
+    let path = favourites_path()
+        .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::NotFound, "no config directory"))?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let text = serde_json::to_string_pretty(favourites)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+    fs::write(path, text)
+}
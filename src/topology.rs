@@ -0,0 +1,391 @@
+// the layout of the tank, described in a json file so different designs can be tried without
+// rebuilding the plugin. see topologies/ for examples
+
+use serde::{Deserialize, Serialize};
+
+use crate::dsp::processor::{Feedback, Mix, Parallel, Processor, Series};
+use crate::dsp::{Allpass, DegradedDelay, Delay, OnePole};
+use crate::shared::SHARED;
+use crate::storage;
+
+// the layout used when there's no topology.json in the user directory
+const DEFAULT_TOPOLOGY: &str = include_str!("../topologies/schroeder.json");
+
+// the rate the times in a topology file are written for
+const REFERENCE_RATE: f32 = 44100.0;
+// the most samples the tanks are prepared to process at once
+const MAX_BLOCK: usize = 1024;
+
+fn one() -> f32 {
+    1.0
+}
+
+// times are in samples at REFERENCE_RATE, like the layout this replaced, and get scaled to the rate
+// the tanks are built for so the tail sounds the same at any rate
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Node {
+    Delay {
+        time: i32,
+    },
+    // unless it's fixed, `g` scales the "sort of length" parameter
+    Allpass {
+        time: i32,
+        #[serde(default = "one")]
+        g: f32,
+        #[serde(default)]
+        fixed: bool,
+    },
+    // follows the damping, degradation, freeze and shimmer parameters. unless it's fixed,
+    // `feedback` scales the "sort of length" parameter
+    DegradedDelay {
+        time: i32,
+        #[serde(default = "one")]
+        feedback: f32,
+        #[serde(default)]
+        fixed: bool,
+    },
+    // a lowpass, following the damping parameter when `amount` is left out
+    Damping {
+        #[serde(default)]
+        amount: Option<f32>,
+    },
+    Gain {
+        gain: f32,
+    },
+    Series {
+        nodes: Vec<Node>,
+    },
+    // every node gets the same input, their outputs are summed
+    Parallel {
+        nodes: Vec<Node>,
+    },
+    Feedback {
+        amount: f32,
+        node: Box<Node>,
+    },
+    Mix {
+        mix: f32,
+        node: Box<Node>,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Topology {
+    pub name: String,
+    pub left: Node,
+    pub right: Node,
+}
+
+impl Default for Topology {
+    fn default() -> Self {
+        serde_json::from_str(DEFAULT_TOPOLOGY).expect("the built in topology should parse")
+    }
+}
+
+impl Topology {
+    pub fn parse(text: &str) -> Result<Self, String> {
+        serde_json::from_str(text).map_err(|e| e.to_string())
+    }
+
+    /// The user's topology.json, or the default if there isn't one. An error if there is one but
+    /// it doesn't parse.
+    pub fn load() -> Result<Self, String> {
+        match storage::read_topology() {
+            Some(text) => Self::parse(&text),
+            None => Ok(Self::default()),
+        }
+    }
+
+    pub fn build(&self, sample_rate: f32) -> Tanks {
+        let mut left = self.left.build(sample_rate);
+        let mut right = self.right.build(sample_rate);
+        left.prepare(sample_rate, MAX_BLOCK);
+        right.prepare(sample_rate, MAX_BLOCK);
+        Tanks {
+            left,
+            right,
+            sample_rate,
+        }
+    }
+}
+
+// shown in the editor until a topology loads without one
+pub fn report_error(error: Option<String>) {
+    if let Ok(mut topology_error) = SHARED.topology_error.lock() {
+        *topology_error = error;
+    }
+}
+
+// both channels, built for one sample rate
+pub struct Tanks {
+    pub left: Unit,
+    pub right: Unit,
+    pub sample_rate: f32,
+}
+
+// the per-sample settings every unit in a tank follows, for one channel
+pub struct Controls {
+    pub g: f32, //"sort of length", with the wacky lfo applied
+    pub damping: f32,
+    pub degrade_amount: i32,
+    pub degrade_ratio: f32,
+    pub freeze: f32,
+    pub shimmer: f32,
+    pub semitones: f32,
+    pub tone: f32,
+}
+
+// a node built for a sample rate and ready to process
+pub enum Unit {
+    Delay(Delay),
+    Allpass {
+        allpass: Allpass,
+        g: f32,
+        fixed: bool,
+    },
+    DegradedDelay {
+        delay: DegradedDelay,
+        feedback: f32,
+        fixed: bool,
+    },
+    Damping {
+        filter: OnePole,
+        amount: Option<f32>,
+    },
+    Gain(f32),
+    Series(Box<Series<Unit, Unit>>),
+    Parallel(Box<Parallel<Unit, Unit>>),
+    Feedback(Box<Feedback<Unit>>),
+    Mix(Box<Mix<Unit>>),
+}
+
+impl Node {
+    pub fn build(&self, sample_rate: f32) -> Unit {
+        // the delay lines hold a quarter of a second
+        let time = |time: i32| {
+            ((time as f32 * sample_rate / REFERENCE_RATE).round() as i32)
+                .clamp(1, (sample_rate / 4.0) as i32 - 1)
+        };
+
+        match self {
+            Node::Delay { time: t } => Unit::Delay(Delay::new(sample_rate, time(*t))),
+            Node::Allpass { time: t, g, fixed } => Unit::Allpass {
+                allpass: Allpass::new(sample_rate, time(*t), *g),
+                g: *g,
+                fixed: *fixed,
+            },
+            Node::DegradedDelay {
+                time: t,
+                feedback,
+                fixed,
+            } => Unit::DegradedDelay {
+                delay: DegradedDelay::new(sample_rate, time(*t), *feedback),
+                feedback: *feedback,
+                fixed: *fixed,
+            },
+            Node::Damping { amount } => Unit::Damping {
+                filter: OnePole::new(amount.unwrap_or(0.0)),
+                amount: *amount,
+            },
+            Node::Gain { gain } => Unit::Gain(*gain),
+            // chained left to right, so the first node is the innermost
+            Node::Series { nodes } => nodes
+                .iter()
+                .map(|node| node.build(sample_rate))
+                .reduce(|first, second| Unit::Series(Box::new(Series::new(first, second))))
+                .unwrap_or(Unit::Gain(1.0)),
+            Node::Parallel { nodes } => nodes
+                .iter()
+                .map(|node| node.build(sample_rate))
+                .reduce(|first, second| Unit::Parallel(Box::new(Parallel::new(first, second))))
+                .unwrap_or(Unit::Gain(0.0)),
+            Node::Feedback { amount, node } => {
+                Unit::Feedback(Box::new(Feedback::new(node.build(sample_rate), *amount)))
+            }
+            Node::Mix { mix, node } => Unit::Mix(Box::new(Mix::new(node.build(sample_rate), *mix))),
+        }
+    }
+}
+
+impl Unit {
+    pub fn apply(&mut self, controls: &Controls) {
+        match self {
+            Unit::Delay(_) | Unit::Gain(_) => {}
+            Unit::Allpass { allpass, g, fixed } => {
+                if !*fixed {
+                    allpass.set_g(*g * controls.g);
+                }
+            }
+            Unit::DegradedDelay {
+                delay,
+                feedback,
+                fixed,
+            } => {
+                if !*fixed {
+                    delay.set_feedback((*feedback * controls.g).clamp(-1.0, 1.0));
+                }
+                delay.set_a(1.0 - controls.damping);
+                delay.set_amt(controls.degrade_amount);
+                delay.set_ratio(controls.degrade_ratio);
+                delay.set_freeze(controls.freeze);
+                delay.set_shimmer(controls.shimmer);
+                delay.set_semitones(controls.semitones);
+                delay.set_tone(controls.tone);
+            }
+            Unit::Damping { filter, amount } => {
+                filter.set_damping(amount.unwrap_or(controls.damping));
+            }
+            Unit::Series(series) => {
+                series.first.apply(controls);
+                series.second.apply(controls);
+            }
+            Unit::Parallel(parallel) => {
+                parallel.first.apply(controls);
+                parallel.second.apply(controls);
+            }
+            Unit::Feedback(feedback) => feedback.inner.apply(controls),
+            Unit::Mix(mix) => mix.inner.apply(controls),
+        }
+    }
+
+    /// 1, 2, 4 or 8, for the degraded delays.
+    pub fn set_oversampling(&mut self, factor: usize) {
+        match self {
+            Unit::DegradedDelay { delay, .. } => delay.set_oversampling(factor),
+            Unit::Series(series) => {
+                series.first.set_oversampling(factor);
+                series.second.set_oversampling(factor);
+            }
+            Unit::Parallel(parallel) => {
+                parallel.first.set_oversampling(factor);
+                parallel.second.set_oversampling(factor);
+            }
+            Unit::Feedback(feedback) => feedback.inner.set_oversampling(factor),
+            Unit::Mix(mix) => mix.inner.set_oversampling(factor),
+            _ => {}
+        }
+    }
+}
+
+impl Processor for Unit {
+    fn prepare(&mut self, sample_rate: f32, max_block: usize) {
+        match self {
+            Unit::Delay(delay) => delay.prepare(sample_rate, max_block),
+            Unit::Allpass { allpass, .. } => allpass.prepare(sample_rate, max_block),
+            Unit::DegradedDelay { delay, .. } => delay.prepare(sample_rate, max_block),
+            Unit::Damping { filter, .. } => filter.prepare(sample_rate, max_block),
+            Unit::Gain(_) => {}
+            Unit::Series(series) => series.prepare(sample_rate, max_block),
+            Unit::Parallel(parallel) => parallel.prepare(sample_rate, max_block),
+            Unit::Feedback(feedback) => feedback.prepare(sample_rate, max_block),
+            Unit::Mix(mix) => mix.prepare(sample_rate, max_block),
+        }
+    }
+
+    fn reset(&mut self) {
+        match self {
+            Unit::Delay(delay) => delay.reset(),
+            Unit::Allpass { allpass, .. } => allpass.reset(),
+            Unit::DegradedDelay { delay, .. } => delay.reset(),
+            Unit::Damping { filter, .. } => filter.reset(),
+            Unit::Gain(_) => {}
+            Unit::Series(series) => series.reset(),
+            Unit::Parallel(parallel) => parallel.reset(),
+            Unit::Feedback(feedback) => feedback.reset(),
+            Unit::Mix(mix) => mix.reset(),
+        }
+    }
+
+    fn process_sample(&mut self, input: f32) -> f32 {
+        match self {
+            Unit::Delay(delay) => delay.process_sample(input),
+            Unit::Allpass { allpass, .. } => allpass.process_sample(input),
+            Unit::DegradedDelay { delay, .. } => delay.process_sample(input),
+            Unit::Damping { filter, .. } => filter.process_sample(input),
+            Unit::Gain(gain) => input * *gain,
+            Unit::Series(series) => series.process_sample(input),
+            Unit::Parallel(parallel) => parallel.process_sample(input),
+            Unit::Feedback(feedback) => feedback.process_sample(input),
+            Unit::Mix(mix) => mix.process_sample(input),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // what each channel's leaves are, in order, with their times
+    fn layout(node: &Node) -> Vec<(&'static str, i32)> {
+        match node {
+            Node::Delay { time } => vec![("delay", *time)],
+            Node::Allpass { time, .. } => vec![("allpass", *time)],
+            Node::DegradedDelay { time, .. } => vec![("degraded_delay", *time)],
+            Node::Damping { .. } => vec![("damping", 0)],
+            Node::Gain { .. } => vec![("gain", 0)],
+            Node::Series { nodes } | Node::Parallel { nodes } => {
+                nodes.iter().flat_map(layout).collect()
+            }
+            Node::Feedback { node, .. } | Node::Mix { node, .. } => layout(node),
+        }
+    }
+
+    fn built_layout(unit: &Unit) -> Vec<&'static str> {
+        match unit {
+            Unit::Delay(_) => vec!["delay"],
+            Unit::Allpass { .. } => vec!["allpass"],
+            Unit::DegradedDelay { .. } => vec!["degraded_delay"],
+            Unit::Damping { .. } => vec!["damping"],
+            Unit::Gain(_) => vec!["gain"],
+            Unit::Series(series) => {
+                [built_layout(&series.first), built_layout(&series.second)].concat()
+            }
+            Unit::Parallel(parallel) => [
+                built_layout(&parallel.first),
+                built_layout(&parallel.second),
+            ]
+            .concat(),
+            Unit::Feedback(feedback) => built_layout(&feedback.inner),
+            Unit::Mix(mix) => built_layout(&mix.inner),
+        }
+    }
+
+    // Topology::default expects this to parse, and it should still be the original four combs in
+    // parallel into three allpasses
+    #[test]
+    fn default_topology_is_the_schroeder_layout() {
+        let topology = Topology::parse(DEFAULT_TOPOLOGY).expect("the default topology parses");
+        let expected = vec![
+            ("degraded_delay", 1323),
+            ("degraded_delay", 1499),
+            ("degraded_delay", 1676),
+            ("degraded_delay", 1852),
+            ("gain", 0),
+            ("allpass", 4410),
+            ("allpass", 2999),
+            ("allpass", 2646),
+        ];
+        for channel in [&topology.left, &topology.right].iter() {
+            match channel {
+                Node::Series { nodes } => match &nodes[0] {
+                    Node::Parallel { nodes } => assert_eq!(nodes.len(), 4),
+                    _ => panic!("the combs should be in parallel"),
+                },
+                _ => panic!("the channel should be a series"),
+            }
+            assert_eq!(layout(channel), expected);
+        }
+
+        let mut tanks = topology.build(REFERENCE_RATE);
+        let names: Vec<_> = expected.iter().map(|(name, _)| *name).collect();
+        for tank in [&mut tanks.left, &mut tanks.right].iter_mut() {
+            assert_eq!(built_layout(tank), names);
+            let output: Vec<f32> = (0..REFERENCE_RATE as usize)
+                .map(|i| tank.process_sample(if i == 0 { 1.0 } else { 0.0 }))
+                .collect();
+            assert!(output.iter().all(|sample| sample.is_finite()));
+            assert!(output.iter().any(|sample| *sample != 0.0));
+        }
+    }
+}